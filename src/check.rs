@@ -0,0 +1,853 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+A subcommand that validates the configuration of a release notes project without building it.
+
+Unlike the build, which stops at the first error, the check reports all the problems
+that it finds, each with the file and the line where the problem occurs.
+
+The YAML parser doesn't record the location of the parsed values, so the check finds
+the line of each problem by searching for the relevant value in the text of the file.
+*/
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, Result, WrapErr};
+use serde::de::DeserializeOwned;
+use serde_yaml::Value;
+
+use crate::config::{
+    self, tracker, ConfigPaths, CustomPaths, KeyOrSearch, Overrides, Section, Template, Variant,
+};
+use crate::templating::id_fragment;
+
+/// A single problem in the project configuration.
+struct Problem {
+    file: PathBuf,
+    /// The line of the problem in the file, starting from 1, if the check can find it.
+    line: Option<usize>,
+    message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// A configuration file, with its text and the problems found in it.
+struct Source {
+    path: PathBuf,
+    text: String,
+    problems: Vec<Problem>,
+}
+
+impl Source {
+    fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Cannot read the configuration file: {}", path.display()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            text,
+            problems: Vec::new(),
+        })
+    }
+
+    /// Record a problem. A value that occurs several times in the file can lead
+    /// to the same problem several times, so skip the problems already recorded.
+    fn report(&mut self, line: Option<usize>, message: String) {
+        if !self
+            .problems
+            .iter()
+            .any(|problem| problem.line == line && problem.message == message)
+        {
+            self.problems.push(Problem {
+                file: self.path.clone(),
+                line,
+                message,
+            });
+        }
+    }
+
+    /// Find the first line, starting from the `from` line, that contains the text.
+    fn line_of(&self, needle: &str, from: usize) -> Option<usize> {
+        self.text
+            .lines()
+            .enumerate()
+            .skip(from.saturating_sub(1))
+            .find(|(_index, line)| line.contains(needle))
+            .map(|(index, _line)| index + 1)
+    }
+
+    /// Find the first line, starting from the `from` line, that sets the field to the value,
+    /// such as `title: Kernel`.
+    fn line_of_field(&self, field: &str, value: &str, from: usize) -> Option<usize> {
+        self.text
+            .lines()
+            .enumerate()
+            .skip(from.saturating_sub(1))
+            .find(|(_index, line)| {
+                line.split_once(&format!("{field}:"))
+                    .is_some_and(|(_key, rest)| rest.contains(value))
+            })
+            .map(|(index, _line)| index + 1)
+    }
+
+    /// Find the lines where the items of the top-level list start.
+    fn top_level_items(&self) -> Vec<usize> {
+        self.text
+            .lines()
+            .enumerate()
+            .filter(|(_index, line)| line.starts_with('-'))
+            .map(|(index, _line)| index + 1)
+            .collect()
+    }
+
+    /// Read the alias, such as `*name`, that starts at the line and column, both counted from 1.
+    fn alias_at(&self, line: usize, column: usize) -> Option<String> {
+        let rest: String = self
+            .text
+            .lines()
+            .nth(line.checked_sub(1)?)?
+            .chars()
+            .skip(column.checked_sub(1)?)
+            .collect();
+        rest.starts_with('*').then(|| {
+            rest.chars()
+                .take_while(|c| !c.is_whitespace() && !matches!(c, ',' | '[' | ']' | '{' | '}'))
+                .collect()
+        })
+    }
+
+    /// Parse the file as YAML and report the parsing error, if any.
+    fn parse<T: DeserializeOwned>(&mut self) -> Option<T> {
+        match serde_yaml::from_str(&self.text) {
+            Ok(parsed) => Some(parsed),
+            Err(error) => {
+                let line = error.location().map(|location| location.line());
+                // The error message ends with the location, which the problem already states.
+                let message = error.to_string();
+                let message = message.split(" at line ").next().unwrap_or_default();
+                let message = if message == "unknown anchor" {
+                    let alias = error
+                        .location()
+                        .and_then(|location| self.alias_at(location.line(), location.column()));
+                    format!(
+                        "The `{}` alias refers to an anchor that isn't declared above it.",
+                        alias.as_deref().unwrap_or("*")
+                    )
+                } else {
+                    format!("Invalid configuration: {message}")
+                };
+                self.report(line, message);
+                None
+            }
+        }
+    }
+}
+
+/// Format a YAML value in a short form for a problem message.
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Number(number) => number.to_string(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .replace('\n', " "),
+    }
+}
+
+/// Check a single ticket query entry, including its references, recursively.
+fn check_entry(
+    source: &mut Source,
+    entry: &Value,
+    line: Option<usize>,
+    trackers: Option<&tracker::Config>,
+) {
+    let Some(items) = entry
+        .as_sequence()
+        .filter(|items| (2..=3).contains(&items.len()))
+    else {
+        source.report(
+            line,
+            format!(
+                "The `{}` entry isn't a list of a tracker, an identifier, and optional options.",
+                describe(entry)
+            ),
+        );
+        return;
+    };
+
+    let service = match serde_yaml::from_value::<tracker::Service>(items[0].clone()) {
+        Ok(service) => Some(service),
+        Err(error) => {
+            source.report(
+                line,
+                format!("The `{}` tracker is unknown: {error}", describe(&items[0])),
+            );
+            None
+        }
+    };
+
+    let using = check_identifier(source, &items[1], line);
+
+    if let (Some(service), Some(using), Some(trackers)) = (service, &using, trackers) {
+        if !trackers.configures(service) {
+            source.report(line, config::missing_tracker(service, using));
+        }
+    }
+
+    if let Some(options) = items.get(2) {
+        check_options(source, options, line, trackers);
+    }
+}
+
+/// Check that the identifier specifies exactly one of a key and a search.
+fn check_identifier(
+    source: &mut Source,
+    identifier: &Value,
+    line: Option<usize>,
+) -> Option<KeyOrSearch> {
+    let Some(mapping) = identifier.as_mapping() else {
+        source.report(
+            line,
+            format!(
+                "The `{}` identifier must specify either a `key` or a `search`.",
+                describe(identifier)
+            ),
+        );
+        return None;
+    };
+
+    for field in mapping.keys() {
+        if !matches!(field.as_str(), Some("key" | "search")) {
+            source.report(
+                line,
+                format!(
+                    "The `{}` field is unknown in the identifier. Use `key` or `search`.",
+                    describe(field)
+                ),
+            );
+        }
+    }
+
+    match (mapping.get("key"), mapping.get("search")) {
+        (Some(key), None) if key.is_string() || key.is_number() => {
+            Some(KeyOrSearch::Key(describe(key)))
+        }
+        (None, Some(Value::String(search))) => Some(KeyOrSearch::Search(search.clone())),
+        (Some(_), Some(_)) => {
+            source.report(
+                line,
+                "The entry specifies both a `key` and a `search`. Specify only one.".to_string(),
+            );
+            None
+        }
+        (None, None) => {
+            source.report(
+                line,
+                "The entry specifies neither a `key` nor a `search`.".to_string(),
+            );
+            None
+        }
+        _ => {
+            source.report(
+                line,
+                "The key must be a string or a number, and the search must be a string."
+                    .to_string(),
+            );
+            None
+        }
+    }
+}
+
+/// Check the overrides and the references in the options of a ticket query entry.
+fn check_options(
+    source: &mut Source,
+    options: &Value,
+    line: Option<usize>,
+    trackers: Option<&tracker::Config>,
+) {
+    let Some(mapping) = options.as_mapping() else {
+        source.report(
+            line,
+            format!(
+                "The `{}` options must contain `overrides` or `references`.",
+                describe(options)
+            ),
+        );
+        return;
+    };
+
+    for (field, value) in mapping {
+        match field.as_str() {
+            Some("overrides") => {
+                if let Err(error) = serde_yaml::from_value::<Overrides>(value.clone()) {
+                    source.report(line, format!("Invalid overrides: {error}"));
+                }
+            }
+            Some("references") => {
+                let Some(references) = value.as_sequence() else {
+                    source.report(
+                        line,
+                        "The references must be a list of tickets.".to_string(),
+                    );
+                    continue;
+                };
+                for reference in references {
+                    // Locate the reference by its key or search, below the referring entry.
+                    let reference_line = reference
+                        .get(1)
+                        .and_then(|identifier| identifier.get("key").or(identifier.get("search")))
+                        .and_then(|needle| source.line_of(&describe(needle), line.unwrap_or(1)))
+                        .or(line);
+                    check_entry(source, reference, reference_line, trackers);
+                }
+            }
+            _ => source.report(
+                line,
+                format!(
+                    "The `{}` option is unknown. Use `overrides` or `references`.",
+                    describe(field)
+                ),
+            ),
+        }
+    }
+}
+
+/// Check all ticket query entries in the tickets configuration.
+fn check_tickets(source: &mut Source, trackers: Option<&tracker::Config>) {
+    let Some(entries) = source.parse::<Value>() else {
+        return;
+    };
+
+    let Some(entries) = entries.as_sequence() else {
+        source.report(None, "The file must contain a list of tickets.".to_string());
+        return;
+    };
+
+    // If the entries aren't written one per line, the lines are unknown.
+    let lines = source.top_level_items();
+    let lines_match = lines.len() == entries.len();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let line = if lines_match {
+            Some(lines[index])
+        } else {
+            None
+        };
+        check_entry(source, entry, line, trackers);
+    }
+}
+
+/// Check the sections, and recursively their subsections, that share the same parent.
+///
+/// Each section comes with its YAML value, which shows the fields that the file sets explicitly.
+fn check_sections(source: &mut Source, sections: &[(&Section, &Value)], from: usize) {
+    // Sibling sections with the same ID would write into the same file.
+    let mut ids: Vec<(String, &str)> = Vec::new();
+    let mut search_from = from;
+
+    for (section, value) in sections {
+        let line = source.line_of_field("title", &section.title, search_from);
+        if let Some(line) = line {
+            // The next sibling starts below this one, even if it has the same title.
+            search_from = line + 1;
+        }
+
+        let id = id_fragment(&section.title);
+        if let Some((_id, other)) = ids.iter().find(|(other_id, _title)| other_id == &id) {
+            source.report(
+                line,
+                format!(
+                    "The `{}` section has the same ID, `{}`, as the `{}` section on the same level.",
+                    section.title, id, other
+                ),
+            );
+        } else {
+            ids.push((id, &section.title));
+        }
+
+        if section.group_by.is_some() && section.subsections.is_some() {
+            source.report(
+                line,
+                format!(
+                    "The `{}` section configures both `group_by` and `subsections`.",
+                    section.title
+                ),
+            );
+        }
+
+        // An explicit, empty filter in a leaf section probably misses its conditions.
+        // A section without a filter takes all the tickets of its parent, which is common
+        // in chapters that only hold subsections.
+        let is_leaf = section.subsections.is_none() && section.group_by.is_none();
+        if is_leaf
+            && !section.catch_all
+            && value.get("filter").is_some()
+            && section.filter.is_empty()
+        {
+            let filter_line = line.and_then(|line| source.line_of("filter", line));
+            source.report(
+                filter_line.or(line),
+                format!("The `{}` section has an empty filter.", section.title),
+            );
+        }
+
+        if let (Some(subsections), Some(values)) = (
+            &section.subsections,
+            value.get("subsections").and_then(Value::as_sequence),
+        ) {
+            let subsections: Vec<(&Section, &Value)> = subsections.iter().zip(values).collect();
+            check_sections(source, &subsections, line.unwrap_or(from));
+        }
+    }
+}
+
+/// Parse each item of a list in the templates configuration separately, so that a problem
+/// in one item doesn't hide the problems in the others.
+///
+/// Returns the valid items with their YAML values, and whether all items are valid.
+fn parse_items<T: DeserializeOwned>(
+    source: &mut Source,
+    items: &Value,
+    key: &str,
+    list_line: Option<usize>,
+) -> (Vec<(T, Value)>, bool) {
+    let Some(items) = items.as_sequence() else {
+        source.report(list_line, format!("The `{key}` entry must be a list."));
+        return (Vec::new(), false);
+    };
+
+    let mut parsed = Vec::new();
+    let mut all_valid = true;
+
+    for item in items {
+        match serde_yaml::from_value(item.clone()) {
+            Ok(valid) => parsed.push((valid, item.clone())),
+            Err(error) => {
+                all_valid = false;
+                // Locate the item by its title or name, below the start of the list.
+                let from = list_line.unwrap_or(1);
+                let line = ["title", "name"]
+                    .into_iter()
+                    .find_map(|field| {
+                        let name = describe(item.get(field)?);
+                        source.line_of_field(field, &name, from)
+                    })
+                    .or(list_line);
+                source.report(line, format!("Invalid item in `{key}`: {error}"));
+            }
+        }
+    }
+
+    (parsed, all_valid)
+}
+
+/// Check the chapters and their sections in the templates configuration.
+///
+/// Each chapter, reusable subsection, and variant is parsed separately,
+/// so that the check reports the problems in all of them at once.
+fn check_templates(source: &mut Source) {
+    let Some(value) = source.parse::<Value>() else {
+        return;
+    };
+    let Some(mapping) = value.as_mapping() else {
+        source.report(
+            None,
+            "The file must contain the template configuration, starting with `chapters`."
+                .to_string(),
+        );
+        return;
+    };
+
+    // Check each of the other top-level fields on its own. The lists are checked item by item.
+    for (key, field) in mapping {
+        let Some(name) = key.as_str() else {
+            continue;
+        };
+        if ["chapters", "subsections", "sections", "variants"].contains(&name) {
+            continue;
+        }
+        let mut single = serde_yaml::Mapping::new();
+        single.insert("chapters".into(), Value::Sequence(Vec::new()));
+        single.insert(key.clone(), field.clone());
+        if let Err(error) = serde_yaml::from_value::<Template>(Value::Mapping(single)) {
+            let line = source.line_of(&format!("{name}:"), 1);
+            source.report(line, format!("Invalid configuration: {error}"));
+        }
+    }
+
+    for key in ["subsections", "sections"] {
+        if let Some(subsections) = mapping.get(key) {
+            let line = source.line_of(&format!("{key}:"), 1);
+            parse_items::<Section>(source, subsections, key, line);
+        }
+    }
+
+    match mapping.get("chapters") {
+        Some(chapters) => {
+            // Start searching for the chapter titles below the reusable subsections.
+            let chapters_line = source.line_of("chapters:", 1);
+            let (chapters, _all_valid) =
+                parse_items::<Section>(source, chapters, "chapters", chapters_line);
+            let chapters: Vec<(&Section, &Value)> = chapters
+                .iter()
+                .map(|(chapter, value)| (chapter, value))
+                .collect();
+            check_sections(source, &chapters, chapters_line.unwrap_or(1));
+        }
+        None => source.report(None, "The `chapters` list is missing.".to_string()),
+    }
+
+    if let Some(variants) = mapping.get("variants") {
+        let variants_line = source.line_of("variants:", 1);
+        let (variants, all_valid) =
+            parse_items::<Variant>(source, variants, "variants", variants_line);
+        // The problems of an incomplete list might be misleading, such as an empty list.
+        if all_valid {
+            let variants: Vec<Variant> = variants
+                .into_iter()
+                .map(|(variant, _value)| variant)
+                .collect();
+            for problem in Variant::problems(&variants) {
+                source.report(variants_line, problem);
+            }
+        }
+    }
+}
+
+/// Check the trackers configuration and return it if it's valid.
+fn check_trackers(source: &mut Source) -> Option<tracker::Config> {
+    let trackers = source.parse();
+
+    // Each tracker parses its own section separately, so the parser can't locate
+    // a problem inside the section. Point to the start of the section instead.
+    let located: Vec<Option<usize>> = source
+        .problems
+        .iter()
+        .map(|problem| {
+            problem.line.or_else(|| {
+                let message = problem.message.strip_prefix("Invalid configuration: ")?;
+                // Such as "unknown tracker `redmine`, ..." or "jira: missing field ...".
+                let section = match message.strip_prefix("unknown tracker `") {
+                    Some(rest) => rest.split_once('`')?.0,
+                    None => message.split_once(": ")?.0,
+                };
+                source.line_of(&format!("{section}:"), 1)
+            })
+        })
+        .collect();
+    for (problem, line) in source.problems.iter_mut().zip(located) {
+        problem.line = line;
+    }
+
+    trackers
+}
+
+/// Check the configuration of the release notes project in the directory
+/// and print out all the problems.
+pub fn check(project_dir: &Path, custom_paths: &CustomPaths) -> Result<()> {
+    let data_dir = config::locate_data_dir(project_dir)?;
+    let paths = ConfigPaths::new(&data_dir, custom_paths);
+
+    let mut trackers_source = Source::read(&paths.trackers)?;
+    let mut tickets_source = Source::read(&paths.tickets)?;
+    let mut templates_source = Source::read(&paths.templates)?;
+
+    let trackers = check_trackers(&mut trackers_source);
+    check_tickets(&mut tickets_source, trackers.as_ref());
+    check_templates(&mut templates_source);
+
+    let problems: Vec<Problem> = [tickets_source, trackers_source, templates_source]
+        .into_iter()
+        .flat_map(|source| source.problems)
+        .collect();
+
+    if problems.is_empty() {
+        println!("The project configuration has no problems.");
+        Ok(())
+    } else {
+        for problem in &problems {
+            println!("{problem}");
+        }
+        bail!(
+            "Found {} problems in the project configuration.",
+            problems.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the check on the configuration text and list the problems as `line: message`.
+    fn problems(check: impl FnOnce(&mut Source), text: &str) -> Vec<String> {
+        let mut source = Source {
+            path: PathBuf::from("test.yaml"),
+            text: text.to_string(),
+            problems: Vec::new(),
+        };
+        check(&mut source);
+        source
+            .problems
+            .iter()
+            .map(|problem| {
+                let line = problem
+                    .line
+                    .map_or("?".to_string(), |line| line.to_string());
+                format!("{line}: {}", problem.message)
+            })
+            .collect()
+    }
+
+    /// Assert that exactly these problems occur, each identified by its line and a part of its message.
+    fn assert_problems(problems: &[String], expected: &[(&str, &str)]) {
+        assert_eq!(problems.len(), expected.len(), "{problems:#?}");
+        for (line, message) in expected {
+            assert!(
+                problems
+                    .iter()
+                    .any(|problem| problem.starts_with(&format!("{line}: "))
+                        && problem.contains(message)),
+                "Missing `{line}: {message}` in {problems:#?}"
+            );
+        }
+    }
+
+    fn trackers() -> Option<tracker::Config> {
+        let mut source = Source {
+            path: PathBuf::from("trackers.yaml"),
+            text: "file:\n  directory: tickets\n".to_string(),
+            problems: Vec::new(),
+        };
+        source.parse()
+    }
+
+    #[test]
+    fn valid_configuration() {
+        let tickets =
+            "- [File, key: one]\n- [Local, search: \"fix-*\", {references: [[file, key: two]]}]\n";
+        assert_problems(
+            &problems(|s| check_tickets(s, trackers().as_ref()), tickets),
+            &[],
+        );
+
+        let templates = "\
+chapters:
+  - title: Features
+    intro_abstract: |
+      Read this: *carefully* before upgrading.
+      The list: [*a*, *b*], or {key: *c}.
+    subsections:
+      - title: New
+        filter: { doc_type: [Enhancement] }
+      - title: Other
+        catch_all: true
+  - title: Grouped
+    group_by: component
+";
+        assert_problems(&problems(check_templates, templates), &[]);
+    }
+
+    #[test]
+    fn aliases() {
+        let text = "\
+common: &common
+  doc_type: [Bug Fix]
+chapters:
+  - title: Fixes
+    filter: *common
+  - title: Other
+    filter: *missing
+";
+        assert_problems(
+            &problems(check_templates, text),
+            &[("7", "The `*missing` alias refers to an anchor")],
+        );
+
+        // The tickets configuration reports a broken alias the same way.
+        let tickets = "- [File, key: one]\n- [Jira, *missing]\n";
+        assert_problems(
+            &problems(|s| check_tickets(s, trackers().as_ref()), tickets),
+            &[("2", "`*missing` alias")],
+        );
+    }
+
+    #[test]
+    fn invalid_yaml() {
+        assert_problems(
+            &problems(|s| check_tickets(s, None), "- [File, key: one\n- x: ]"),
+            &[("2", "Invalid configuration")],
+        );
+        assert_problems(
+            &problems(|s| check_tickets(s, None), "file: x\n"),
+            &[("?", "must contain a list of tickets")],
+        );
+    }
+
+    #[test]
+    fn ticket_entries() {
+        let text = "\
+- just text
+- [Redmine, key: 1]
+- [File, 42]
+- [File, {id: 1, key: 1}]
+- [File, {key: 1, search: x}]
+- [File, {}]
+- [File, key: [1]]
+- [Jira, key: PROJ-1]
+- [File, key: 2, overrides]
+- [File, key: 3, {overrides: {doc_type: 5, unknown: x}}]
+- [File, key: 4, {references: one}]
+- [File, key: 5, {references: [[File, search: nested, {references: [[Jira, key: PROJ-2]]}]]}]
+- [File, key: 6, {cc: x}]
+";
+        assert_problems(
+            &problems(|s| check_tickets(s, trackers().as_ref()), text),
+            &[
+                ("1", "The `just text` entry isn't a list"),
+                ("2", "The `Redmine` tracker is unknown"),
+                (
+                    "3",
+                    "The `42` identifier must specify either a `key` or a `search`",
+                ),
+                ("4", "The `id` field is unknown in the identifier"),
+                ("5", "specifies both a `key` and a `search`"),
+                ("6", "specifies neither a `key` nor a `search`"),
+                ("7", "The key must be a string or a number"),
+                ("8", "requires the `jira` section"),
+                ("9", "The `overrides` options must contain"),
+                ("10", "Invalid overrides"),
+                ("11", "The references must be a list of tickets"),
+                (
+                    "12",
+                    "The [Jira, key: PROJ-2] ticket requires the `jira` section",
+                ),
+                ("13", "The `cc` option is unknown"),
+            ],
+        );
+    }
+
+    #[test]
+    fn sections() {
+        let text = "\
+chapters:
+  - title: New features
+    subsections:
+      - title: Kernel
+        filter: { component: [kernel] }
+      - title: kernel
+        filter: { component: [kernel-rt] }
+  - title: Empty
+    filter: {}
+  - title: Both
+    group_by: component
+    subsections:
+      - title: Inside
+        filter: { component: [x] }
+  - title: Holder
+    filter: {}
+    subsections:
+      - title: Leaf without a filter
+";
+        assert_problems(
+            &problems(check_templates, text),
+            &[
+                (
+                    "6",
+                    "The `kernel` section has the same ID, `kernel`, as the `Kernel` section",
+                ),
+                ("9", "The `Empty` section has an empty filter"),
+                ("10", "configures both `group_by` and `subsections`"),
+            ],
+        );
+    }
+
+    #[test]
+    fn templates_structure() {
+        assert_problems(
+            &problems(check_templates, "- title: x\n"),
+            &[("?", "must contain the template configuration")],
+        );
+        assert_problems(
+            &problems(check_templates, "format: pdf\n"),
+            &[
+                ("1", "Invalid configuration: unknown variant `pdf`"),
+                ("?", "The `chapters` list is missing"),
+            ],
+        );
+
+        let text = "\
+chapters:
+  - title: Good
+  - title: Bad
+    sort_by: color
+  - unknown: field
+variants:
+  - name: internal
+  - name: internal
+";
+        assert_problems(
+            &problems(check_templates, text),
+            &[
+                ("3", "Invalid item in `chapters`"),
+                ("1", "Invalid item in `chapters`"),
+                ("6", "The `internal` variant is declared more than once"),
+                ("6", "shares the `internal` output directory"),
+            ],
+        );
+    }
+
+    #[test]
+    fn trackers_configuration() {
+        let check = |text: &str| {
+            let mut valid = false;
+            let found = problems(|source| valid = check_trackers(source).is_some(), text);
+            (valid, found)
+        };
+
+        let (valid, found) = check("file:\n  directory: tickets\n");
+        assert!(valid, "{found:#?}");
+
+        let text = "\
+file:
+  directory: tickets
+jira:
+  host: https://jira.example.com
+  fields:
+    doc_type: [customfield_1]
+";
+        let (valid, found) = check(text);
+        assert!(!valid);
+        assert_problems(&found, &[("3", "jira: missing field `doc_text`")]);
+
+        let (valid, found) = check("file:\n  directory: tickets\nredmine:\n  host: x\n");
+        assert!(!valid);
+        assert_problems(&found, &[("3", "unknown tracker `redmine`")]);
+    }
+}
@@ -44,22 +44,40 @@ pub enum Commands {
     /// Build release notes from a configuration directory.
     #[bpaf(command)]
     Build {
+        /// Rebuild from the ticket snapshot of the previous build, without accessing ticket trackers.
+        #[bpaf(long, switch)]
+        offline: bool,
+        /// A configuration file containing tickets. The default is `acorns/tickets.yaml`.
+        #[bpaf(short('t'), long, argument("FILE"))]
+        tickets: Option<PathBuf>,
+        /// A configuration file containing trackers. The default is `acorns/trackers.yaml`.
+        #[bpaf(short('T'), long, argument("FILE"))]
+        trackers: Option<PathBuf>,
+        /// A configuration file containing templates. The default is `acorns/templates.yaml`.
+        #[bpaf(short('e'), long, argument("FILE"))]
+        templates: Option<PathBuf>,
+        /// The directory for the generated files. The default is `acorns/generated`.
+        #[bpaf(short('o'), long, argument("DIR"))]
+        output: Option<PathBuf>,
         /// Path to the configuration directory. The default is the current working directory.
         #[bpaf(positional::<PathBuf>("DIR"), fallback(".".into()))]
         project: PathBuf,
-        // Disabling the optional config paths for now.
-        // It's questionable if it's even useful to specify these separately.
-        /*
-        /// A configuration file containing tickets.
-        #[clap(short, long, value_name = "FILE")]
+    },
+    /// Check the configuration of a project and report all problems, without building it.
+    #[bpaf(command)]
+    Check {
+        /// A configuration file containing tickets. The default is `acorns/tickets.yaml`.
+        #[bpaf(short('t'), long, argument("FILE"))]
         tickets: Option<PathBuf>,
-        /// A configuration file containing trackers.
-        #[clap(short='T', long, value_name = "FILE")]
+        /// A configuration file containing trackers. The default is `acorns/trackers.yaml`.
+        #[bpaf(short('T'), long, argument("FILE"))]
         trackers: Option<PathBuf>,
-        /// A configuration file containing templates.
-        #[clap(short='e', long, value_name = "FILE")]
+        /// A configuration file containing templates. The default is `acorns/templates.yaml`.
+        #[bpaf(short('e'), long, argument("FILE"))]
         templates: Option<PathBuf>,
-        */
+        /// Path to the configuration directory. The default is the current working directory.
+        #[bpaf(positional::<PathBuf>("DIR"), fallback(".".into()))]
+        project: PathBuf,
     },
     /// Query a single ticket.
     #[bpaf(command)]
@@ -82,6 +100,24 @@ pub enum Commands {
         #[bpaf(positional::<String>("ID"))]
         id: String,
     },
+    /// Compare the tickets in two builds, using their ticket snapshots or JSON status outputs.
+    #[bpaf(command)]
+    Diff {
+        /// The trackers configuration file, which is necessary to process ticket snapshots.
+        #[bpaf(
+            short,
+            long,
+            argument("FILE"),
+            fallback("./acorns/trackers.yaml".into())
+        )]
+        config: PathBuf,
+        /// The ticket snapshot or JSON status output of the earlier build.
+        #[bpaf(positional::<PathBuf>("OLD"))]
+        old: PathBuf,
+        /// The ticket snapshot or JSON status output of the later build.
+        #[bpaf(positional::<PathBuf>("NEW"))]
+        new: PathBuf,
+    },
     /// Convert a CoRN 3 configuration file to the new format.
     #[bpaf(command)]
     Convert {
@@ -16,13 +16,19 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::convert::From;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 
-use color_eyre::eyre::{eyre, Result, WrapErr};
-use serde::Deserialize;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use regex::{Regex, RegexBuilder};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+
+use crate::custom_templates::ModuleTemplates;
 
 /// The name of this program, as specified in Cargo.toml. Used later to access configuration files.
 const PROGRAM_NAME: &str = env!("CARGO_PKG_NAME");
@@ -53,12 +59,21 @@ pub struct TicketQuery {
 ///
 /// * `Key`: Requests a specific ticket by its key.
 /// * `Free`: Requests all tickets that match a free-form query.
-#[derive(Debug, Eq, PartialEq, Hash, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum KeyOrSearch {
     Key(String),
     Search(String),
 }
 
+impl fmt::Display for KeyOrSearch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "key: {key}"),
+            Self::Search(search) => write!(f, "search: {search:?}"),
+        }
+    }
+}
+
 /// A ticket query as defined in the user configuration file.
 /// This entry struct is separate from `TicketQuery` because
 /// this tuple format is more ergonomic to write in config files,
@@ -73,27 +88,64 @@ struct TicketQueryEntry(
     #[serde(default)] TicketQueryOptions,
 );
 
-impl From<TicketQueryEntry> for TicketQuery {
-    fn from(item: TicketQueryEntry) -> Self {
+impl TryFrom<TicketQueryEntry> for TicketQuery {
+    type Error = QueryError;
+
+    fn try_from(item: TicketQueryEntry) -> Result<Self, QueryError> {
         // Destructure all the parts of the query to avoid trouble with partial moves
         // and to avoid cloning.
         let (tracker, identifier, options) = (item.0, item.1, item.2);
         let references: Vec<Arc<TicketQuery>> = options
             .references
             .into_iter()
-            .map(Self::from)
-            .map(Arc::new)
-            .collect();
+            .map(|reference| Self::try_from(reference).map(Arc::new))
+            .collect::<Result<_, _>>()?;
 
-        Self {
-            using: identifier.into(),
+        let using =
+            KeyOrSearch::try_from(identifier).map_err(|problem| QueryError { tracker, problem })?;
+
+        Ok(Self {
+            using,
             tracker,
             overrides: options.overrides,
             references,
+        })
+    }
+}
+
+/// A ticket query entry that doesn't specify exactly one of a key and a search.
+#[derive(Debug)]
+pub struct QueryError {
+    tracker: tracker::Service,
+    problem: IdentifierError,
+}
+
+/// The problem with the identifier of a ticket query entry.
+#[derive(Debug)]
+pub enum IdentifierError {
+    KeyAndSearch { key: String, search: String },
+    Neither,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.problem {
+            IdentifierError::KeyAndSearch { key, search } => write!(
+                f,
+                "The [{}, key: {}, search: {:?}] ticket entry specifies both a key and a search. Specify only one.",
+                self.tracker, key, search
+            ),
+            IdentifierError::Neither => write!(
+                f,
+                "A {} ticket entry specifies neither a key nor a search.",
+                self.tracker
+            ),
         }
     }
 }
 
+impl std::error::Error for QueryError {}
+
 /// The string that identifies tickets to pull from the tracker,
 /// either in the form of a ticket key (which can be a string or a number),
 /// or in the form of a search string.
@@ -111,13 +163,18 @@ struct Identifier {
     search: Option<String>,
 }
 
-impl From<Identifier> for KeyOrSearch {
-    fn from(item: Identifier) -> Self {
-        match (item.key.clone(), item.search.clone()) {
-            (Some(key), None) => KeyOrSearch::Key(key.into_string()),
-            (None, Some(search)) => KeyOrSearch::Search(search),
-            (Some(_), Some(_)) => panic!("Please specify only one entry:\n{item:#?}"),
-            (None, None) => panic!("Please specify at least one entry:\n{item:#?}"),
+impl TryFrom<Identifier> for KeyOrSearch {
+    type Error = IdentifierError;
+
+    fn try_from(item: Identifier) -> Result<Self, IdentifierError> {
+        match (item.key, item.search) {
+            (Some(key), None) => Ok(KeyOrSearch::Key(key.into_string())),
+            (None, Some(search)) => Ok(KeyOrSearch::Search(search)),
+            (Some(key), Some(search)) => Err(IdentifierError::KeyAndSearch {
+                key: key.into_string(),
+                search,
+            }),
+            (None, None) => Err(IdentifierError::Neither),
         }
     }
 }
@@ -164,35 +221,87 @@ pub struct Overrides {
 }
 
 pub mod tracker {
-    use serde::{Deserialize, Serialize};
+    use color_eyre::eyre::{eyre, Error, Result};
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use std::any::Any;
+    use std::collections::{BTreeMap, HashMap};
     use std::fmt;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+
+    use crate::extra_fields::DocTextStatus;
+    use crate::tracker_access::{registered, TRACKERS};
 
     /// An issue-tracking service, as in the platform.
-    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
-    pub enum Service {
-        #[serde(alias = "BZ")]
-        Bugzilla,
-        Jira,
+    ///
+    /// Each tracker in `tracker_access::TRACKERS` declares its service.
+    /// The configuration files and the command line refer to the service by any of its names.
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct Service {
+        /// The full name of the service, which also identifies it in the ticket snapshot.
+        pub name: &'static str,
+        /// The short name or acronym of the service, if any. Otherwise, the regular name.
+        pub short_name: &'static str,
+        /// The name of the section that configures the service in the trackers configuration.
+        pub config_section: &'static str,
+        /// Other names of the service that the configuration files accept.
+        pub aliases: &'static [&'static str],
     }
 
     impl fmt::Display for Service {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let name = match self {
-                Self::Bugzilla => "Bugzilla",
-                Self::Jira => "Jira",
-            };
-            write!(f, "{name}")
+            write!(f, "{}", self.name)
+        }
+    }
+
+    impl FromStr for Service {
+        type Err = Error;
+
+        /// Recognize the service from any of its names.
+        /// The comparison is case-insensitive.
+        fn from_str(s: &str) -> Result<Self> {
+            TRACKERS
+                .iter()
+                .map(|tracker| tracker.service())
+                .find(|service| service.names().any(|name| name.eq_ignore_ascii_case(s)))
+                .ok_or_else(|| {
+                    eyre!(
+                        "Unsupported ticket tracker: {:?}. Use one of: {}.",
+                        s,
+                        config_sections()
+                    )
+                })
         }
     }
 
+    /// List the configuration sections of all trackers for an error message.
+    fn config_sections() -> String {
+        let sections: Vec<String> = TRACKERS
+            .iter()
+            .map(|tracker| format!("`{}`", tracker.service().config_section))
+            .collect();
+        sections.join(", ")
+    }
+
     impl Service {
-        /// Return the short name or acronym of the service, if any.
-        /// Otherwise, return the regular name.
-        pub fn short_name(self) -> &'static str {
-            match self {
-                Self::Bugzilla => "BZ",
-                Self::Jira => "Jira",
-            }
+        /// All the names that identify the service.
+        fn names(self) -> impl Iterator<Item = &'static str> {
+            [self.name, self.short_name, self.config_section]
+                .into_iter()
+                .chain(self.aliases.iter().copied())
+        }
+    }
+
+    impl Serialize for Service {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.name)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Service {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let name = String::deserialize(deserializer)?;
+            name.parse().map_err(de::Error::custom)
         }
     }
 
@@ -203,12 +312,21 @@ pub mod tracker {
     pub struct BugzillaFields {
         pub doc_type: Vec<String>,
         pub doc_text: Vec<String>,
+        /// If the doc text is missing or empty, use the bug description instead.
+        #[serde(default)]
+        pub description_fallback: bool,
+        /// Download the bug description to show it in the variants with debug information.
+        #[serde(default)]
+        pub description: bool,
         pub doc_text_status: Vec<String>,
         /// This field is optional. Runtime decides if we need it.
         pub subsystems: Option<Vec<String>>,
         /// These fields are standard, but you can override them.
         pub target_release: Option<Vec<String>>,
         pub docs_contact: Option<Vec<String>>,
+        /// Any other fields by name, each with its own configured IDs.
+        #[serde(default)]
+        pub custom: BTreeMap<String, Vec<String>>,
     }
 
     /// The required fields in the Jira configuration.
@@ -218,12 +336,40 @@ pub mod tracker {
     pub struct JiraFields {
         pub doc_type: Vec<String>,
         pub doc_text: Vec<String>,
+        /// Convert the Jira wiki markup in the doc text to AsciiDoc.
+        /// Release notes are usually written in AsciiDoc, so the conversion is opt-in.
+        #[serde(default)]
+        pub wiki_markup: bool,
         pub doc_text_status: Vec<String>,
-        pub docs_contact: Vec<String>,
+        /// This field is optional. Without it, release notes have no docs contact.
+        pub docs_contact: Option<Vec<String>>,
         /// This field is optional. Runtime decides if we need it.
         pub subsystems: Option<Vec<String>>,
         /// This field is standard, but you can override it.
         pub target_release: Option<Vec<String>>,
+        /// Any other fields by name, each with its own configured IDs.
+        #[serde(default)]
+        pub custom: BTreeMap<String, Vec<String>>,
+    }
+
+    /// The fields in the GitHub or GitLab configuration.
+    ///
+    /// Forge issues have no custom fields. Instead, each field lists sources in the issue,
+    /// which are either labels with a prefix, such as `label:doc-type/`,
+    /// or sections of the issue description under a heading, such as `section:Release note`.
+    #[derive(Debug, Eq, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct ForgeFields {
+        pub doc_type: Vec<String>,
+        pub doc_text: Vec<String>,
+        pub doc_text_status: Vec<String>,
+        /// These fields are optional.
+        pub subsystems: Option<Vec<String>>,
+        pub target_release: Option<Vec<String>>,
+        pub docs_contact: Option<Vec<String>>,
+        /// Any other fields by name, each with its own configured IDs.
+        #[serde(default)]
+        pub custom: BTreeMap<String, Vec<String>>,
     }
 
     /// The particular instance of an issue tracker,
@@ -234,6 +380,8 @@ pub mod tracker {
         pub host: String,
         pub api_key: Option<String>,
         pub fields: BugzillaFields,
+        #[serde(default)]
+        pub doc_text_status_values: DocTextStatusValues,
     }
 
     /// The particular instance of an issue tracker,
@@ -244,14 +392,122 @@ pub mod tracker {
         pub host: String,
         pub api_key: Option<String>,
         pub fields: JiraFields,
+        #[serde(default)]
+        pub doc_text_status_values: DocTextStatusValues,
     }
 
-    /// The issue tracker instances configured in the current release notes project.
+    /// The particular instance of a Git forge, with the URL to its REST API and access credentials.
     #[derive(Debug, Eq, PartialEq, Deserialize)]
     #[serde(deny_unknown_fields)]
+    pub struct ForgeInstance {
+        pub host: String,
+        pub api_key: Option<String>,
+        pub fields: ForgeFields,
+        #[serde(default)]
+        pub doc_text_status_values: DocTextStatusValues,
+    }
+
+    /// The directory that contains local ticket files.
+    #[derive(Debug, Eq, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct FileInstance {
+        /// A relative path starts in the directory with the trackers configuration file.
+        pub directory: PathBuf,
+        #[serde(default)]
+        pub doc_text_status_values: DocTextStatusValues,
+    }
+
+    /// The values of the doc text status field that the tracker uses,
+    /// on top of the values that acorns recognizes by default.
+    ///
+    /// The comparison is case-insensitive, and the configured values take precedence
+    /// over the default ones.
+    #[derive(Debug, Default, Eq, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct DocTextStatusValues {
+        #[serde(default)]
+        pub approved: Vec<String>,
+        #[serde(default)]
+        pub in_progress: Vec<String>,
+        #[serde(default)]
+        pub no_documentation: Vec<String>,
+        /// The status of tickets with an unrecognized value. If it's set, an unrecognized value
+        /// is only a warning. Otherwise, it's an error.
+        pub unknown: Option<DocTextStatus>,
+    }
+
+    /// The configuration of a single tracker, as the tracker parses it.
+    pub trait Section: Any + fmt::Debug {
+        fn as_any(&self) -> &dyn Any;
+        fn as_any_mut(&mut self) -> &mut dyn Any;
+    }
+
+    impl<T: Any + fmt::Debug> Section for T {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    /// The issue tracker instances configured in the current release notes project.
+    /// A project only needs to configure the trackers that its tickets use.
+    ///
+    /// Each top-level key in the trackers configuration file names a tracker
+    /// in `tracker_access::TRACKERS`, which parses its own section.
+    #[derive(Debug)]
     pub struct Config {
-        pub jira: JiraInstance,
-        pub bugzilla: BugzillaInstance,
+        sections: HashMap<Service, Box<dyn Section>>,
+    }
+
+    impl Config {
+        /// Check if the trackers configuration contains the section for this service.
+        pub fn configures(&self, service: Service) -> bool {
+            self.sections.contains_key(&service)
+        }
+
+        /// The configuration of this service, if the project configures it.
+        pub fn section<T: Any>(&self, service: Service) -> Option<&T> {
+            self.sections
+                .get(&service)
+                .and_then(|section| section.as_ref().as_any().downcast_ref())
+        }
+
+        /// Resolve the relative paths in the configuration against the directory
+        /// that contains the trackers configuration file.
+        pub fn resolve_paths(&mut self, config_dir: &Path) {
+            for (service, section) in &mut self.sections {
+                if let Some(tracker) = registered(*service) {
+                    tracker.resolve_paths(section.as_mut(), config_dir);
+                }
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Config {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let entries = BTreeMap::<String, serde_yaml::Value>::deserialize(deserializer)?;
+            let mut sections = HashMap::new();
+
+            for (name, value) in entries {
+                let tracker = TRACKERS
+                    .iter()
+                    .find(|tracker| tracker.service().config_section == name)
+                    .ok_or_else(|| {
+                        de::Error::custom(format!(
+                            "unknown tracker `{name}`, expected one of {}",
+                            config_sections()
+                        ))
+                    })?;
+                let section = tracker
+                    .parse_config(value)
+                    .map_err(|error| de::Error::custom(format!("{name}: {error}")))?;
+                sections.insert(tracker.service(), section);
+            }
+
+            Ok(Self { sections })
+        }
     }
 
     /// Generalize over the different required fields in the Bugzilla and Jira configuration.
@@ -269,6 +525,20 @@ pub mod tracker {
         fn doc_text_status(&self) -> &[String];
         /// The configured names of the docs contact field.
         fn docs_contact(&self) -> &[String];
+        /// The configured values of the doc text status field.
+        fn doc_text_status_values(&self) -> &DocTextStatusValues;
+        /// Fall back on the ticket description if the doc text is missing or empty.
+        /// Only Bugzilla supports the fallback.
+        fn description_fallback(&self) -> bool {
+            false
+        }
+        /// Convert the Jira wiki markup in the doc text to AsciiDoc.
+        /// Only Jira has the wiki markup.
+        fn wiki_markup(&self) -> bool {
+            false
+        }
+        /// The configured names of the custom fields, by the name that the project uses.
+        fn custom(&self) -> &BTreeMap<String, Vec<String>>;
         /// The configured URL to the instance host.
         fn host(&self) -> &str;
     }
@@ -277,6 +547,9 @@ pub mod tracker {
         fn doc_type(&self) -> &[String] {
             &self.fields.doc_type
         }
+        fn description_fallback(&self) -> bool {
+            self.fields.description_fallback
+        }
         fn doc_text_status(&self) -> &[String] {
             &self.fields.doc_text_status
         }
@@ -303,6 +576,12 @@ pub mod tracker {
                 None => &[],
             }
         }
+        fn doc_text_status_values(&self) -> &DocTextStatusValues {
+            &self.doc_text_status_values
+        }
+        fn custom(&self) -> &BTreeMap<String, Vec<String>> {
+            &self.fields.custom
+        }
         fn host(&self) -> &str {
             &self.host
         }
@@ -312,11 +591,52 @@ pub mod tracker {
         fn doc_type(&self) -> &[String] {
             &self.fields.doc_type
         }
+        fn wiki_markup(&self) -> bool {
+            self.fields.wiki_markup
+        }
         fn doc_text_status(&self) -> &[String] {
             &self.fields.doc_text_status
         }
-        /// The docs contact field is standard in Jira, but you can override it.
+        fn target_release(&self) -> &[String] {
+            match &self.fields.target_release {
+                Some(field) => field,
+                None => &[],
+            }
+        }
+        fn subsystems(&self) -> &[String] {
+            match &self.fields.subsystems {
+                Some(field) => field,
+                None => &[],
+            }
+        }
+        fn doc_text(&self) -> &[String] {
+            &self.fields.doc_text
+        }
         /// An empty slice signifies that the user entered no configuration.
+        fn docs_contact(&self) -> &[String] {
+            match &self.fields.docs_contact {
+                Some(field) => field,
+                None => &[],
+            }
+        }
+        fn doc_text_status_values(&self) -> &DocTextStatusValues {
+            &self.doc_text_status_values
+        }
+        fn custom(&self) -> &BTreeMap<String, Vec<String>> {
+            &self.fields.custom
+        }
+        fn host(&self) -> &str {
+            &self.host
+        }
+    }
+
+    impl FieldsConfig for ForgeInstance {
+        fn doc_type(&self) -> &[String] {
+            &self.fields.doc_type
+        }
+        fn doc_text_status(&self) -> &[String] {
+            &self.fields.doc_text_status
+        }
         fn target_release(&self) -> &[String] {
             match &self.fields.target_release {
                 Some(field) => field,
@@ -333,12 +653,55 @@ pub mod tracker {
             &self.fields.doc_text
         }
         fn docs_contact(&self) -> &[String] {
-            &self.fields.docs_contact
+            match &self.fields.docs_contact {
+                Some(field) => field,
+                None => &[],
+            }
+        }
+        fn doc_text_status_values(&self) -> &DocTextStatusValues {
+            &self.doc_text_status_values
+        }
+        fn custom(&self) -> &BTreeMap<String, Vec<String>> {
+            &self.fields.custom
         }
         fn host(&self) -> &str {
             &self.host
         }
     }
+
+    /// Local ticket files contain the final values of all fields, so there are no fields to configure.
+    impl FieldsConfig for FileInstance {
+        fn doc_type(&self) -> &[String] {
+            &[]
+        }
+        fn doc_text_status(&self) -> &[String] {
+            &[]
+        }
+        fn target_release(&self) -> &[String] {
+            &[]
+        }
+        fn subsystems(&self) -> &[String] {
+            &[]
+        }
+        fn doc_text(&self) -> &[String] {
+            &[]
+        }
+        fn docs_contact(&self) -> &[String] {
+            &[]
+        }
+        fn doc_text_status_values(&self) -> &DocTextStatusValues {
+            &self.doc_text_status_values
+        }
+        /// Ticket files set their custom fields directly.
+        fn custom(&self) -> &BTreeMap<String, Vec<String>> {
+            static NO_FIELDS: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            &NO_FIELDS
+        }
+        /// The directory takes the place of the host.
+        fn host(&self) -> &str {
+            self.directory.to_str().unwrap_or_default()
+        }
+    }
 }
 
 /// This struct models the template configuration file.
@@ -352,6 +715,162 @@ pub struct Template {
     pub chapters: Vec<Section>,
     #[serde(alias = "sections")]
     pub subsections: Option<Vec<Section>>,
+    /// Fail the build if a ticket is unused in the document, or used more than once.
+    #[serde(default)]
+    pub strict: bool,
+    /// The markup language of the generated document.
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Also assemble a complete document in a single file for each variant.
+    #[serde(default)]
+    pub single_file: bool,
+    /// The title of the single-file document.
+    pub document_title: Option<String>,
+    /// The variants of the document that the build generates from the same tickets.
+    #[serde(default = "Variant::defaults")]
+    pub variants: Vec<Variant>,
+}
+
+/// A variant of the generated document, which selects its own tickets
+/// and saves its files in its own output subdirectory.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Variant {
+    pub name: String,
+    /// Selects the tickets that appear in this variant. An empty filter selects all tickets.
+    #[serde(default)]
+    pub filter: Filter,
+    /// Show the debug information, such as the docs contact and the doc text status,
+    /// at each release note.
+    #[serde(default)]
+    pub debug: bool,
+    /// The output subdirectory of this variant. The default is the name of the variant.
+    directory: Option<String>,
+}
+
+impl Variant {
+    /// The variants that a project generates unless it configures its own:
+    ///
+    /// * `internal`: All tickets, with debug information, intended for preparing the release notes.
+    /// * `external`: Only tickets with an approved release note, intended for publishing.
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::internal(), Self::external()]
+    }
+
+    pub fn internal() -> Self {
+        Self {
+            name: "internal".to_string(),
+            filter: Filter::default(),
+            debug: true,
+            directory: None,
+        }
+    }
+
+    pub fn external() -> Self {
+        let approved = "approved"
+            .parse()
+            .expect("The built-in doc text status pattern is invalid. This is a bug.");
+
+        Self {
+            name: "external".to_string(),
+            filter: Filter {
+                doc_text_status: Some(vec![approved]),
+                ..Filter::default()
+            },
+            debug: false,
+            directory: None,
+        }
+    }
+
+    /// The output subdirectory of this variant.
+    pub fn directory(&self) -> &str {
+        self.directory.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Describe each problem with the configured variants, such as two variants
+    /// that share a name or an output subdirectory.
+    pub fn problems(variants: &[Self]) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if variants.is_empty() {
+            problems
+                .push("The `variants` list is empty, so the build generates nothing.".to_string());
+        }
+
+        for (index, variant) in variants.iter().enumerate() {
+            let earlier = &variants[..index];
+            let directory = variant.directory();
+
+            if earlier.iter().any(|other| other.name == variant.name) {
+                problems.push(format!(
+                    "The `{variant}` variant is declared more than once."
+                ));
+            }
+            if earlier.iter().any(|other| other.directory() == directory) {
+                problems.push(format!(
+                    "The `{variant}` variant shares the `{directory}` output directory with another variant."
+                ));
+            }
+            // The directory is a single name inside the output directory.
+            if directory.is_empty()
+                || directory == "."
+                || directory == ".."
+                || directory.contains(['/', '\\'])
+            {
+                problems.push(format!(
+                    "The `{variant}` variant has an invalid output directory: `{directory}`."
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The markup language of the generated document.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    #[serde(alias = "adoc")]
+    AsciiDoc,
+    #[serde(alias = "md")]
+    Markdown,
+}
+
+impl OutputFormat {
+    /// The file extension of the generated files in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::AsciiDoc => "adoc",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+impl Template {
+    /// Describe each problem with the configured variants, such as two variants
+    /// that share a name or an output subdirectory.
+    pub fn variant_problems(&self) -> Vec<String> {
+        Variant::problems(&self.variants)
+    }
+
+    /// Check if any section in the chapters, at any level, collects the uncaught tickets.
+    pub fn has_catch_all(&self) -> bool {
+        fn any_catch_all(sections: &[Section]) -> bool {
+            sections.iter().any(|section| {
+                section.catch_all || section.subsections.as_deref().is_some_and(any_catch_all)
+            })
+        }
+
+        any_catch_all(&self.chapters)
+    }
 }
 
 /// This struct covers the necessary properties of a section, which can either
@@ -360,26 +879,215 @@ pub struct Template {
 ///
 /// The `filter` field narrows down the tickets that can appear in this module
 /// or in the modules that are included in this assembly.
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Default, PartialEq, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Section {
     pub title: String,
     pub intro_abstract: Option<String>,
+    #[serde(default)]
     pub filter: Filter,
     #[serde(alias = "sections")]
     pub subsections: Option<Vec<Section>>,
+    /// Only include the tickets that no other section includes.
+    #[serde(default)]
+    pub catch_all: bool,
+    /// Leave out the tickets that an earlier section in the document already includes.
+    #[serde(default)]
+    pub exclusive: bool,
+    /// The order of the release notes. If it's not set, the section uses the order of its parent.
+    pub sort_by: Option<SortBy>,
+    pub sort_order: Option<SortOrder>,
+    /// Generate a subsection for each value of this property among the tickets.
+    pub group_by: Option<GroupBy>,
+    /// The titles of the generated subsections, by the value.
+    #[serde(default)]
+    pub group_titles: HashMap<String, String>,
+    /// The title of the generated subsections for values that have no configured title,
+    /// where `{value}` stands for the value. The default title is the value.
+    pub group_fallback: Option<String>,
+}
+
+/// The property of the tickets that divides a section into generated subsections.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    Component,
+    Subsystem,
+    Label,
+    DocType,
+}
+
+/// The property of the tickets that orders the release notes in a section:
+///
+/// * `None`: The order of the tickets configuration.
+/// * `Summary`: The ticket summary.
+/// * `Title`: The title of the release note, which is the first line of the doc text.
+/// * `Priority`: The ticket priority, from the most urgent.
+/// * `Component`: The first component of the ticket.
+/// * `Id`: The tracker and the key of the ticket.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    None,
+    Summary,
+    Title,
+    Priority,
+    Component,
+    Id,
+}
+
+/// The direction of the sort order.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    #[serde(alias = "asc")]
+    Ascending,
+    #[serde(alias = "desc")]
+    Descending,
 }
 
 /// The configuration of a filter, which narrows down the tickets
 /// that can appear in the section that the filter belongs to.
-#[derive(Debug, Eq, PartialEq, Deserialize)]
+///
+/// Each list of patterns matches the tickets that have a value that matches any of the patterns.
+/// A ticket has to match all the configured lists and combinators.
+#[derive(Debug, Default, Eq, PartialEq, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Filter {
-    pub doc_type: Option<Vec<String>>,
-    pub subsystem: Option<Vec<String>>,
-    pub component: Option<Vec<String>>,
+    pub doc_type: Option<Vec<Pattern>>,
+    pub subsystem: Option<Vec<Pattern>>,
+    pub component: Option<Vec<Pattern>>,
+    pub label: Option<Vec<Pattern>>,
+    pub flag: Option<Vec<Pattern>>,
+    pub priority: Option<Vec<Pattern>>,
+    pub product: Option<Vec<Pattern>>,
+    pub target_release: Option<Vec<Pattern>>,
+    pub status: Option<Vec<Pattern>>,
+    pub doc_text_status: Option<Vec<Pattern>>,
+    /// Accepts any name of the tracker that `tickets.yaml` accepts, such as `BZ`.
+    pub tracker: Option<Vec<tracker::Service>>,
+    /// Matches the values of the custom fields, by the name of the field.
+    /// A ticket that doesn't set the field doesn't match.
+    pub custom: Option<BTreeMap<String, Vec<Pattern>>>,
+    /// Matches the tickets that are public, or the tickets that aren't.
+    pub public: Option<bool>,
+    /// Excludes the tickets that match this nested filter.
+    pub not: Option<Box<Filter>>,
+    /// Matches the tickets that match any of these nested filters.
+    /// An empty list matches no ticket.
+    pub any: Option<Vec<Filter>>,
+    /// Matches the tickets that match all of these nested filters.
+    /// An empty list matches every ticket.
+    pub all: Option<Vec<Filter>>,
+}
+
+impl Filter {
+    /// The filter configures no condition, or only empty lists of values.
+    pub fn is_empty(&self) -> bool {
+        let lists = [
+            &self.doc_type,
+            &self.subsystem,
+            &self.component,
+            &self.label,
+            &self.flag,
+            &self.priority,
+            &self.product,
+            &self.target_release,
+            &self.status,
+            &self.doc_text_status,
+        ];
+
+        lists
+            .iter()
+            .all(|list| list.as_ref().map_or(true, Vec::is_empty))
+            && self.tracker.as_ref().map_or(true, Vec::is_empty)
+            && self
+                .custom
+                .as_ref()
+                .map_or(true, |fields| fields.values().all(Vec::is_empty))
+            && self.public.is_none()
+            && self.not.is_none()
+            && self.any.as_ref().map_or(true, Vec::is_empty)
+            && self.all.as_ref().map_or(true, Vec::is_empty)
+    }
+}
+
+/// A value in a filter, which matches a ticket value regardless of letter case.
+///
+/// The pattern is compiled once, when the templates configuration is parsed:
+///
+/// * A value enclosed in slashes, such as `/^kernel-.*/`, is a regular expression.
+///   It matches if it finds a match anywhere in the ticket value.
+/// * A value that contains `*` or `?` is a glob, such as `sst_net*`.
+///   It must match the complete ticket value.
+/// * Any other value must equal the complete ticket value.
+#[derive(Debug)]
+pub struct Pattern {
+    /// The value as written in the configuration.
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn matches(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
 }
 
+impl FromStr for Pattern {
+    type Err = regex::Error;
+
+    fn from_str(source: &str) -> Result<Self, regex::Error> {
+        let expression = if let Some(expression) = source
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+            .filter(|expression| !expression.is_empty())
+        {
+            expression.to_string()
+        } else if source.contains(['*', '?']) {
+            let glob: String = source
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_string(),
+                    '?' => ".".to_string(),
+                    other => regex::escape(&other.to_string()),
+                })
+                .collect();
+            format!("^{glob}$")
+        } else {
+            format!("^{}$", regex::escape(source))
+        };
+
+        let regex = RegexBuilder::new(&expression)
+            .case_insensitive(true)
+            .build()?;
+
+        Ok(Self {
+            source: source.to_string(),
+            regex,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        source.parse().map_err(|error| {
+            de::Error::custom(format!("invalid filter pattern {source:?}: {error}"))
+        })
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
 /// Parse the specified tickets config file into the ticket queries configuration.
 fn parse_tickets(tickets_file: &Path) -> Result<Vec<TicketQuery>> {
     let text =
@@ -388,31 +1096,82 @@ fn parse_tickets(tickets_file: &Path) -> Result<Vec<TicketQuery>> {
         serde_yaml::from_str(&text).wrap_err("Cannot parse the tickets configuration file.")?;
     log::debug!("{:#?}", config);
 
-    let queries = config.into_iter().map(TicketQuery::from).collect();
+    let queries = config
+        .into_iter()
+        .map(TicketQuery::try_from)
+        .collect::<Result<_, _>>()
+        .wrap_err("Invalid entry in the tickets configuration file.")?;
 
     Ok(queries)
 }
 
 /// Parse the specified tracker file into the trackers configuration.
-fn parse_trackers(trackers_file: &Path) -> Result<tracker::Config> {
+pub fn parse_trackers(trackers_file: &Path) -> Result<tracker::Config> {
     let text = fs::read_to_string(trackers_file)
         .wrap_err("Cannot read the trackers configuration file.")?;
-    let trackers: tracker::Config =
+    let mut trackers: tracker::Config =
         serde_yaml::from_str(&text).wrap_err("Cannot parse the trackers configuration file.")?;
     log::debug!("{:#?}", trackers);
 
+    // Resolve the paths, such as the local tickets directory, relative to the trackers configuration file.
+    if let Some(config_dir) = trackers_file.parent() {
+        trackers.resolve_paths(config_dir);
+    }
+
     Ok(trackers)
 }
 
 /// Parse the template configuration files into template structs, with chapter and section definitions.
-fn parse_templates(template_file: &Path) -> Result<Template> {
+pub fn parse_templates(template_file: &Path) -> Result<Template> {
     let text = fs::read_to_string(template_file).wrap_err("Cannot read the template file.")?;
     let templates: Template =
         serde_yaml::from_str(&text).wrap_err("Cannot parse the template file.")?;
     log::debug!("{:#?}", templates);
+
+    let problems = templates.variant_problems();
+    if !problems.is_empty() {
+        bail!(
+            "Invalid variants in the template file:\n* {}",
+            problems.join("\n* ")
+        );
+    }
+
     Ok(templates)
 }
 
+/// The paths to the configuration files of a release notes project.
+pub struct ConfigPaths {
+    pub tickets: PathBuf,
+    pub trackers: PathBuf,
+    pub templates: PathBuf,
+}
+
+impl ConfigPaths {
+    /// The configuration files in the data directory of the project,
+    /// unless the custom paths replace some of them.
+    pub fn new(data_dir: &Path, custom: &CustomPaths) -> Self {
+        let path = |custom: &Option<PathBuf>, default: &str| {
+            custom.clone().unwrap_or_else(|| data_dir.join(default))
+        };
+
+        Self {
+            tickets: path(&custom.tickets, "tickets.yaml"),
+            trackers: path(&custom.trackers, "trackers.yaml"),
+            templates: path(&custom.templates, "templates.yaml"),
+        }
+    }
+}
+
+/// The paths that the user sets on the command line instead of the default locations in the project.
+#[derive(Default)]
+pub struct CustomPaths {
+    pub tickets: Option<PathBuf>,
+    pub trackers: Option<PathBuf>,
+    pub templates: Option<PathBuf>,
+    /// The directory for the generated files.
+    pub output: Option<PathBuf>,
+}
+
 /// Parsed input metadata that represent the configuration of a release notes project
 pub struct Project {
     pub base_dir: PathBuf,
@@ -420,35 +1179,62 @@ pub struct Project {
     pub tickets: Vec<Arc<TicketQuery>>,
     pub trackers: tracker::Config,
     pub templates: Template,
+    /// The custom module templates that override the built-in ones.
+    pub module_templates: ModuleTemplates,
 }
 
 impl Project {
     /// Set up a Project configuration, including parsed configuration files
     /// and paths to the relevant project directories.
-    pub fn new(directory: &Path) -> Result<Self> {
+    pub fn new(directory: &Path, custom_paths: &CustomPaths) -> Result<Self> {
         let abs_path = directory.canonicalize()?;
         let data_dir = locate_data_dir(directory)?;
-        let generated_dir = data_dir.join(GENERATED_PREFIX);
+        let generated_dir = custom_paths
+            .output
+            .clone()
+            .unwrap_or_else(|| data_dir.join(GENERATED_PREFIX));
+
+        // The build removes all files in the output directory that it doesn't generate,
+        // so the output directory must not contain the project itself.
+        let abs_generated_dir = if generated_dir.exists() {
+            generated_dir.canonicalize()?
+        } else {
+            generated_dir.clone()
+        };
+        if abs_path.starts_with(&abs_generated_dir) {
+            bail!(
+                "The output directory must not contain the project: {}",
+                generated_dir.display()
+            );
+        }
 
         // Prepare to access each configuration file.
-        // TODO: Possibly enable overriding the default config paths.
-        let tickets_path = data_dir.join("tickets.yaml");
-        let trackers_path = data_dir.join("trackers.yaml");
-        let templates_path = data_dir.join("templates.yaml");
+        let paths = ConfigPaths::new(&data_dir, custom_paths);
 
         log::debug!(
-            "Configuration files:\n* {}\n* {}\n* {}",
-            tickets_path.display(),
-            trackers_path.display(),
-            templates_path.display()
+            "Configuration files:\n* {}\n* {}\n* {}\nOutput directory: {}",
+            paths.tickets.display(),
+            paths.trackers.display(),
+            paths.templates.display(),
+            generated_dir.display()
         );
 
-        let tickets = parse_tickets(&tickets_path)?
+        let tickets: Vec<Arc<TicketQuery>> = parse_tickets(&paths.tickets)?
             .into_iter()
             .map(Arc::new)
             .collect();
-        let trackers = parse_trackers(&trackers_path)?;
-        let templates = parse_templates(&templates_path)?;
+        let trackers = parse_trackers(&paths.trackers)?;
+        let templates = parse_templates(&paths.templates)?;
+        // The custom module templates are next to the templates configuration file.
+        let templates_dir = paths
+            .templates
+            .parent()
+            .unwrap_or(&data_dir)
+            .join("templates");
+        let module_templates = ModuleTemplates::load(&templates_dir, templates.format)?;
+
+        // Report configuration mismatches before any download starts.
+        validate_trackers(&tickets, &trackers)?;
 
         Ok(Self {
             base_dir: abs_path,
@@ -456,15 +1242,61 @@ impl Project {
             tickets,
             trackers,
             templates,
+            module_templates,
         })
     }
 }
 
+/// Check that the trackers configuration contains a section for every tracker
+/// that the ticket queries and their references use.
+///
+/// Reports all the mismatches at once.
+fn validate_trackers(queries: &[Arc<TicketQuery>], trackers: &tracker::Config) -> Result<()> {
+    let mut problems = Vec::new();
+
+    // Check the release note queries and the reference queries.
+    for query in queries {
+        missing_trackers(query, trackers, &mut problems);
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        bail!(
+            "The tickets configuration doesn't match the trackers configuration:\n{}",
+            problems.join("\n")
+        )
+    }
+}
+
+/// Record the problems of a ticket query and of its references at any depth
+/// whose tracker has no section in the trackers configuration.
+fn missing_trackers(query: &TicketQuery, trackers: &tracker::Config, problems: &mut Vec<String>) {
+    if !trackers.configures(query.tracker) {
+        problems.push(format!(
+            "* {}",
+            missing_tracker(query.tracker, &query.using)
+        ));
+    }
+
+    for reference in &query.references {
+        missing_trackers(reference, trackers, problems);
+    }
+}
+
+/// Describe a ticket query whose tracker has no section in the trackers configuration.
+pub fn missing_tracker(tracker: tracker::Service, using: &KeyOrSearch) -> String {
+    format!(
+        "The [{}, {}] ticket requires the `{}` section in the trackers configuration.",
+        tracker, using, tracker.config_section
+    )
+}
+
 /// Find the base data and configuration directory.
 ///
 /// The directory is based on the current program name, and if not present, it might
 /// fall back on the legacy program name.
-fn locate_data_dir(directory: &Path) -> Result<PathBuf> {
+pub fn locate_data_dir(directory: &Path) -> Result<PathBuf> {
     let abs_path = directory.canonicalize()?;
     let data_dir = abs_path.join(DATA_PREFIX);
 
@@ -492,3 +1324,81 @@ fn locate_data_dir(directory: &Path) -> Result<PathBuf> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check if the pattern matches the value.
+    fn matches(pattern: &str, value: &str) -> Result<bool> {
+        Ok(pattern.parse::<Pattern>()?.matches(value))
+    }
+
+    #[test]
+    fn plain_values_match_completely() -> Result<()> {
+        assert!(matches("kernel", "kernel")?);
+        assert!(!matches("kernel", "kernel-rt")?);
+        assert!(!matches("kernel", "my-kernel")?);
+        Ok(())
+    }
+
+    #[test]
+    fn literal_metacharacters() -> Result<()> {
+        assert!(matches("c++", "C++")?);
+        assert!(matches("a.b", "a.b")?);
+        assert!(!matches("a.b", "axb")?);
+        assert!(matches("(x)|[y]", "(x)|[y]")?);
+        assert!(!matches("(x)|[y]", "x")?);
+        // A slash on one side, or an empty expression, is a plain value.
+        assert!(matches("/usr", "/usr")?);
+        assert!(matches("//", "//")?);
+        Ok(())
+    }
+
+    #[test]
+    fn globs() -> Result<()> {
+        assert!(matches("sst_net*", "sst_networking")?);
+        assert!(matches("sst_net*", "sst_net")?);
+        assert!(!matches("sst_net*", "my_sst_networking")?);
+        assert!(matches("kernel-?", "kernel-5")?);
+        assert!(!matches("kernel-?", "kernel-10")?);
+        // The other characters in a glob are literal.
+        assert!(matches("c++*", "c++17")?);
+        assert!(!matches("a.b*", "axbc")?);
+        Ok(())
+    }
+
+    #[test]
+    fn regular_expressions() -> Result<()> {
+        assert!(matches("/^kernel-/", "kernel-rt")?);
+        assert!(!matches("/^kernel-/", "my-kernel-rt")?);
+        // Without anchors, the expression matches anywhere in the value.
+        assert!(matches("/rt/", "kernel-rt-debug")?);
+        assert!(matches("/^(nfs|xfs)$/", "xfs")?);
+        assert!(!matches("/^(nfs|xfs)$/", "xfsprogs")?);
+        Ok(())
+    }
+
+    #[test]
+    fn case_insensitive_matching() -> Result<()> {
+        assert!(matches("Bug Fix", "bug fix")?);
+        assert!(matches("SST_Net*", "sst_networking")?);
+        assert!(matches("/^KERNEL/", "kernel-rt")?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_regular_expressions() {
+        assert!("/[unclosed/".parse::<Pattern>().is_err());
+        assert!("/(a/".parse::<Pattern>().is_err());
+
+        let error = serde_yaml::from_str::<Filter>("component: [\"/[unclosed/\"]")
+            .err()
+            .map(|error| error.to_string())
+            .unwrap_or_default();
+        assert!(
+            error.contains("invalid filter pattern \"/[unclosed/\""),
+            "{error}"
+        );
+    }
+}
@@ -13,6 +13,7 @@ use regex::Regex;
 use serde::Deserialize;
 
 use crate::config::{tracker::Service, KeyOrSearch};
+use crate::tracker_access::{Bugzilla, Jira, Tracker};
 
 /// A shared error message that displays if the static regular expressions
 /// are invalid, and the regex library can't parse them.
@@ -153,9 +154,9 @@ impl TryFrom<CornEntry> for String {
         let (service, key_or_search) = parse_stamp(&item.id)?;
 
         let prefix = match key_or_search {
-            KeyOrSearch::Key(key) => format!("[{}, key: {key}", service.short_name()),
+            KeyOrSearch::Key(key) => format!("[{}, key: {key}", service.short_name),
             KeyOrSearch::Search(search) => {
-                format!("[{}, search: \"{search}\"", service.short_name())
+                format!("[{}, search: \"{search}\"", service.short_name)
             }
         };
 
@@ -206,19 +207,19 @@ impl TryFrom<CornEntry> for String {
 fn parse_stamp(stamp: &str) -> Result<(Service, KeyOrSearch)> {
     // Supported options
     if let Some(captures) = BZ_REGEX.captures(stamp) {
-        let service = Service::Bugzilla;
+        let service = Bugzilla::SERVICE;
         let key = KeyOrSearch::Key(captures[1].to_string());
         Ok((service, key))
     } else if let Some(captures) = JIRA_REGEX.captures(stamp) {
-        let service = Service::Jira;
+        let service = Jira::SERVICE;
         let key = KeyOrSearch::Key(captures[1].to_string());
         Ok((service, key))
     } else if let Some(captures) = BZ_QUERY_REGEX.captures(stamp) {
-        let service = Service::Bugzilla;
+        let service = Bugzilla::SERVICE;
         let search = KeyOrSearch::Search(captures[1].to_string());
         Ok((service, search))
     } else if let Some(captures) = JIRA_QUERY_REGEX.captures(stamp) {
-        let service = Service::Jira;
+        let service = Jira::SERVICE;
         let search = KeyOrSearch::Search(captures[1].to_string());
         Ok((service, search))
     // Unsupported options
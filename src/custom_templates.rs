@@ -0,0 +1,227 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+Templates in the release notes project that override the built-in templates of the generated modules
+and the built-in layout of release notes.
+
+The built-in templates are compiled into the program, so the project can only replace them
+with templates that load at run time. These templates render with the Jinja engine in `minijinja`,
+with a few adjustments that keep them compatible with the syntax of the built-in templates:
+
+* `{{ signatures|join(", ") }}` and `{{ signatures.join(", ") }}` both join a list of strings.
+  A single value joins as a list of one item.
+* An empty value, such as a missing doc text, inserts nothing.
+* An unknown variable is an error in the output, but it's false in an `if` condition,
+  so that a template can check for an optional variable.
+* The output isn't escaped, and the final line break of the file is left out.
+*/
+
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use minijinja::value::{Value, ValueKind};
+use minijinja::{AutoEscape, Environment, Error, ErrorKind, UndefinedBehavior};
+
+use crate::config::OutputFormat;
+
+/// The templates that can override the built-in module templates,
+/// loaded from the `templates` directory in the project configuration.
+#[derive(Default)]
+pub struct ModuleTemplates {
+    /// Overrides `reference.adoc`, the leaf module with release notes.
+    pub reference: Option<CustomTemplate>,
+    /// Overrides `assembly.adoc`, the assembly that includes other modules.
+    pub assembly: Option<CustomTemplate>,
+    /// Overrides `summary-list.adoc`, the appendix that lists tickets by component.
+    pub summary_list: Option<CustomTemplate>,
+    /// Replaces the built-in layout of each release note.
+    pub release_note: Option<CustomTemplate>,
+}
+
+impl ModuleTemplates {
+    /// Load the templates that exist in the directory. A missing directory means no overrides.
+    ///
+    /// The file names use the extension of the output format, such as `reference.md` for Markdown.
+    pub fn load(directory: &Path, format: OutputFormat) -> Result<Self> {
+        let load = |name: &str| -> Result<Option<CustomTemplate>> {
+            let path = directory.join(format!("{name}.{}", format.extension()));
+            if path.is_file() {
+                log::info!("Using the custom template: {}", path.display());
+                CustomTemplate::from_file(&path).map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+
+        Ok(Self {
+            reference: load("reference")?,
+            assembly: load("assembly")?,
+            summary_list: load("summary-list")?,
+            release_note: load("release-note")?,
+        })
+    }
+}
+
+/// A template loaded from a file in the project, ready to render.
+#[derive(Debug)]
+pub struct CustomTemplate {
+    name: String,
+    environment: Environment<'static>,
+}
+
+impl CustomTemplate {
+    fn from_file(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Cannot read the template: {}", path.display()))?;
+        Self::parse(&path.display().to_string(), text)
+    }
+
+    /// Parse the template text. The name identifies the template in errors.
+    fn parse(name: &str, text: String) -> Result<Self> {
+        let mut environment = Environment::new();
+        // The generated files are AsciiDoc or Markdown, never HTML.
+        environment.set_auto_escape_callback(|_name| AutoEscape::None);
+        environment.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+        environment.set_formatter(|output, state, value| {
+            if value.is_none() {
+                Ok(())
+            } else {
+                minijinja::escape_formatter(output, state, value)
+            }
+        });
+        environment.add_filter("join", join);
+        // The built-in templates join lists with the method syntax of Rust.
+        environment.set_unknown_method_callback(|state, value, method, args| {
+            if method == "join" {
+                let mut filter_args = vec![value.clone()];
+                filter_args.extend_from_slice(args);
+                state.apply_filter("join", &filter_args)
+            } else {
+                Err(Error::from(ErrorKind::UnknownMethod))
+            }
+        });
+
+        environment
+            .add_template_owned(name.to_string(), text)
+            .wrap_err_with(|| format!("Invalid template: {name}"))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            environment,
+        })
+    }
+
+    /// Render the template with the variables in the context, which is a JSON object.
+    pub fn render(&self, context: &serde_json::Value) -> Result<String> {
+        self.environment
+            .get_template(&self.name)
+            .and_then(|template| template.render(context))
+            .wrap_err_with(|| format!("Failed to render the template: {}", self.name))
+    }
+}
+
+/// Join the items of a list with the separator.
+/// A single value joins as a list of one item, which is useful with custom fields
+/// that are a list in some tickets and a single value in others.
+fn join(value: &Value, separator: Option<&str>) -> Result<String, Error> {
+    if value.is_undefined() {
+        return Err(Error::new(
+            ErrorKind::UndefinedError,
+            "cannot join an undefined value",
+        ));
+    }
+
+    let items: Vec<Value> = match value.kind() {
+        ValueKind::Seq | ValueKind::Iterable => value.try_iter()?.collect(),
+        ValueKind::None => Vec::new(),
+        _ => vec![value.clone()],
+    };
+    let items: Vec<String> = items
+        .iter()
+        .filter(|item| !item.is_none())
+        .map(ToString::to_string)
+        .collect();
+
+    Ok(items.join(separator.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(text: &str, context: &serde_json::Value) -> Result<String> {
+        CustomTemplate::parse("test.adoc", text.to_string())?.render(context)
+    }
+
+    #[test]
+    fn loops_and_conditionals() -> Result<()> {
+        let text = "{% for note in notes %}{% if note.title %}.{{ note.title }}\n{% else %}\
+                    (untitled)\n{% endif %}{% endfor %}";
+        let context = json!({ "notes": [{ "title": "First" }, { "title": "" }] });
+        assert_eq!(render(text, &context)?, ".First\n(untitled)\n");
+        Ok(())
+    }
+
+    #[test]
+    fn join_filter_and_method() -> Result<()> {
+        let context = json!({ "list": ["a", "b"], "single": "c", "empty": null });
+        assert_eq!(render("{{ list|join(\", \") }}", &context)?, "a, b");
+        assert_eq!(render("{{ list.join(\", \") }}", &context)?, "a, b");
+        assert_eq!(render("{{ single|join(\", \") }}", &context)?, "c");
+        assert_eq!(render("[{{ empty|join(\", \") }}]", &context)?, "[]");
+        Ok(())
+    }
+
+    #[test]
+    fn empty_values_and_newlines() -> Result<()> {
+        let context = json!({ "text": "<b>", "nothing": null });
+        assert_eq!(render("{{ text }}{{ nothing }}\n", &context)?, "<b>");
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_variables() -> Result<()> {
+        let context = json!({});
+        assert_eq!(
+            render("{% if missing %}yes{% else %}no{% endif %}", &context)?,
+            "no"
+        );
+
+        let error = render("first line\n{{ missing }}", &context)
+            .err()
+            .ok_or_else(|| color_eyre::eyre::eyre!("An unknown variable rendered."))?;
+        let message = format!("{error:#}");
+        assert!(message.contains("test.adoc:2"), "{message}");
+        Ok(())
+    }
+
+    #[test]
+    fn syntax_errors() -> Result<()> {
+        let error = render("first line\nsecond line\n{% for item in %}", &json!({}))
+            .err()
+            .ok_or_else(|| color_eyre::eyre::eyre!("An invalid template parsed."))?;
+        let message = format!("{error:#}");
+        assert!(message.contains("Invalid template: test.adoc"), "{message}");
+        assert!(message.contains("test.adoc:3"), "{message}");
+        Ok(())
+    }
+}
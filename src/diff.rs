@@ -0,0 +1,304 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+A subcommand that compares the tickets in two builds of the same release notes project.
+
+Each build is represented either by its ticket snapshot, or by its JSON status output.
+*/
+
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config;
+use crate::snapshot;
+use crate::ticket_abstraction::{AbstractTicket, TicketId};
+use crate::tracker_access;
+
+/// The properties of a ticket that affect its release note in the document.
+#[derive(PartialEq)]
+struct TicketState {
+    id: TicketId,
+    summary: String,
+    doc_type: String,
+    doc_text: String,
+    doc_text_status: String,
+    components: Vec<String>,
+    subsystems: Vec<String>,
+}
+
+impl From<AbstractTicket> for TicketState {
+    fn from(item: AbstractTicket) -> Self {
+        Self {
+            id: (*item.id).clone(),
+            summary: item.summary,
+            doc_type: item.doc_type,
+            doc_text: item.doc_text,
+            doc_text_status: item.doc_text_status.to_string(),
+            components: item.components,
+            // Invalid subsystems compare the same as no subsystems.
+            subsystems: item.subsystems.unwrap_or_default(),
+        }
+    }
+}
+
+/// A ticket as it appears in the JSON status output.
+#[derive(Deserialize)]
+struct StatusTicket {
+    id: String,
+    summary: String,
+    doc_type: String,
+    doc_text: String,
+    doc_text_status: String,
+    components: Vec<String>,
+    subsystems: Result<Vec<String>, String>,
+}
+
+impl TryFrom<StatusTicket> for TicketState {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(item: StatusTicket) -> Result<Self> {
+        Ok(Self {
+            id: item.id.parse()?,
+            summary: item.summary,
+            doc_type: item.doc_type,
+            doc_text: item.doc_text,
+            doc_text_status: item.doc_text_status,
+            components: item.components,
+            subsystems: item.subsystems.unwrap_or_default(),
+        })
+    }
+}
+
+/// The part of the JSON status output that lists the tickets.
+#[derive(Deserialize)]
+struct StatusOutput {
+    tickets_with_checks: Vec<(StatusTicket, IgnoredAny)>,
+}
+
+/// Load the tickets from a file, which is either a ticket snapshot, or a JSON status output.
+///
+/// Snapshots store raw tickets, so processing them requires the trackers configuration.
+fn load_tickets(file: &Path, trackers_file: &Path) -> Result<Vec<TicketState>> {
+    let text = fs::read_to_string(file)
+        .wrap_err_with(|| format!("Cannot read the file: {}", file.display()))?;
+    let json: Value = serde_json::from_str(&text)
+        .wrap_err_with(|| format!("The file is not valid JSON: {}", file.display()))?;
+
+    // The JSON status output is an object, whereas the snapshot is a list.
+    let tickets: Vec<TicketState> = if json.is_object() {
+        let status: StatusOutput = serde_json::from_value(json)
+            .wrap_err_with(|| format!("Cannot parse the JSON status: {}", file.display()))?;
+        status
+            .tickets_with_checks
+            .into_iter()
+            .map(|(ticket, _checks)| TicketState::try_from(ticket))
+            .collect::<Result<_>>()?
+    } else {
+        let trackers = config::parse_trackers(trackers_file)?;
+        let raw_tickets = snapshot::load_all(file)?;
+        tracker_access::unsorted_tickets(raw_tickets, &trackers)?
+            .into_iter()
+            .map(|annotated| TicketState::from(annotated.ticket))
+            .collect()
+    };
+
+    // A ticket can appear several times, such as when several queries or templates include it.
+    let mut unique: Vec<TicketState> = Vec::new();
+    for ticket in tickets {
+        if !unique.iter().any(|t| t.id == ticket.id) {
+            unique.push(ticket);
+        }
+    }
+
+    Ok(unique)
+}
+
+/// Describe the properties that differ between two states of the same ticket.
+fn changes(old: &TicketState, new: &TicketState) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if old.doc_type != new.doc_type {
+        changes.push(format!("Doc type: {} → {}", old.doc_type, new.doc_type));
+    }
+    if old.doc_text_status != new.doc_text_status {
+        changes.push(format!(
+            "Doc text status: {} → {}",
+            old.doc_text_status, new.doc_text_status
+        ));
+    }
+    if old.components != new.components {
+        changes.push(format!(
+            "Components: {} → {}",
+            old.components.join(", "),
+            new.components.join(", ")
+        ));
+    }
+    if old.subsystems != new.subsystems {
+        changes.push(format!(
+            "Subsystems: {} → {}",
+            old.subsystems.join(", "),
+            new.subsystems.join(", ")
+        ));
+    }
+    if old.doc_text != new.doc_text {
+        changes.push(format!(
+            "Doc text:\n{}",
+            text_diff(&old.doc_text, &new.doc_text)
+        ));
+    }
+
+    changes
+}
+
+/// A line diff of two doc texts, in the order of the lines: lists each removed and added line
+/// at its place, around the longest common sequence of lines. Unchanged lines are left out.
+///
+/// A line that moved shows as removed and added, and so does a repeated line.
+fn text_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // The length of the longest common sequence of the lines that follow each pair of positions.
+    let mut common = vec![vec![0_usize; new.len() + 1]; old.len() + 1];
+    for (i, old_line) in old.iter().enumerate().rev() {
+        for (j, new_line) in new.iter().enumerate().rev() {
+            common[i][j] = if old_line == new_line {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("    - {}", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("    + {}", new[j]));
+            j += 1;
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Compare the tickets in two builds and print out the differences.
+pub fn diff(old_file: &Path, new_file: &Path, trackers_file: &Path) -> Result<()> {
+    let old_tickets = load_tickets(old_file, trackers_file)?;
+    let new_tickets = load_tickets(new_file, trackers_file)?;
+
+    let find = |tickets: &'_ [TicketState], id: &TicketId| -> Option<usize> {
+        tickets.iter().position(|ticket| &ticket.id == id)
+    };
+
+    let added: Vec<&TicketState> = new_tickets
+        .iter()
+        .filter(|ticket| find(&old_tickets, &ticket.id).is_none())
+        .collect();
+    let removed: Vec<&TicketState> = old_tickets
+        .iter()
+        .filter(|ticket| find(&new_tickets, &ticket.id).is_none())
+        .collect();
+    let changed: Vec<(&TicketState, Vec<String>)> = new_tickets
+        .iter()
+        .filter_map(|new| {
+            let old = &old_tickets[find(&old_tickets, &new.id)?];
+            let changes = changes(old, new);
+            if changes.is_empty() {
+                None
+            } else {
+                Some((new, changes))
+            }
+        })
+        .collect();
+
+    if added.is_empty() && removed.is_empty() && changed.is_empty() {
+        println!("No differences.");
+        return Ok(());
+    }
+
+    for (heading, tickets) in [("Added tickets:", &added), ("Removed tickets:", &removed)] {
+        if !tickets.is_empty() {
+            println!("{heading}\n");
+            for ticket in tickets {
+                println!("* {}: {}", ticket.id, ticket.summary);
+            }
+            println!();
+        }
+    }
+
+    if !changed.is_empty() {
+        println!("Changed tickets:\n");
+        for (ticket, changes) in changed {
+            println!("* {}: {}", ticket.id, ticket.summary);
+            for change in changes {
+                println!("  ** {change}");
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_lines_in_place() {
+        let old = "First.\nSecond.\nThird.";
+        let new = "First.\nSecond, changed.\nThird.\nFourth.";
+
+        assert_eq!(
+            text_diff(old, new),
+            "    - Second.\n    + Second, changed.\n    + Fourth."
+        );
+        assert_eq!(text_diff(old, old), "");
+    }
+
+    #[test]
+    fn reordered_lines() {
+        assert_eq!(
+            text_diff("One.\nTwo.\nThree.", "Three.\nOne.\nTwo."),
+            "    + Three.\n    - Three."
+        );
+    }
+
+    #[test]
+    fn repeated_lines() {
+        assert_eq!(
+            text_diff("Step.\nDone.", "Step.\nStep.\nDone."),
+            "    + Step."
+        );
+        assert_eq!(text_diff("+\nText.\n+", "+\nText."), "    - +");
+    }
+}
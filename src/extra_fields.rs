@@ -16,6 +16,7 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::string::ToString;
@@ -24,16 +25,19 @@ use color_eyre::{
     eyre::{bail, eyre},
     Report, Result,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::value::Value;
 
 use bugzilla_query::Bug;
 use jira_query::Issue;
 
 use crate::config::tracker;
+use crate::forges::ForgeIssue;
+use crate::jira_markup;
 
 /// The status or progress of the release note.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DocTextStatus {
     Approved,
     InProgress,
@@ -55,6 +59,63 @@ impl TryFrom<&str> for DocTextStatus {
     }
 }
 
+impl DocTextStatus {
+    /// Recognize the doc text status from its value in the tracker.
+    ///
+    /// Try the values configured for the tracker first, and then the default values.
+    /// If the value is still unrecognized, fall back on the configured status
+    /// for unknown values with a warning, if the tracker sets one.
+    pub fn from_value(
+        value: &str,
+        config: &impl tracker::FieldsConfig,
+        id: impl fmt::Display,
+    ) -> Result<Self> {
+        let values = config.doc_text_status_values();
+        let lowercase = value.to_lowercase();
+        let configured = [
+            (Self::Approved, &values.approved),
+            (Self::InProgress, &values.in_progress),
+            (Self::NoDocumentation, &values.no_documentation),
+        ];
+
+        for (status, strings) in configured {
+            if strings
+                .iter()
+                .any(|string| string.to_lowercase() == lowercase)
+            {
+                return Ok(status);
+            }
+        }
+
+        match (Self::try_from(value), values.unknown) {
+            (Ok(status), _) => Ok(status),
+            (Err(_), Some(default)) => {
+                log::warn!(
+                    "Unrecognized doc text status value in {}: {:?}. Using the {} status.",
+                    id,
+                    value,
+                    default.names()[1]
+                );
+                Ok(default)
+            }
+            (Err(error), None) => Err(error.wrap_err(format!(
+                "Cannot recognize the doc text status in {id}. \
+                Add the value to `doc_text_status_values` in the trackers configuration, \
+                or set the `unknown` status there."
+            ))),
+        }
+    }
+
+    /// The names that filters recognize for this status: the displayed name and the full name.
+    pub fn names(self) -> [&'static str; 2] {
+        match self {
+            Self::Approved => ["Done", "approved"],
+            Self::InProgress => ["WIP", "in progress"],
+            Self::NoDocumentation => ["No docs", "no documentation"],
+        }
+    }
+}
+
 impl fmt::Display for DocTextStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let display = match self {
@@ -105,6 +166,90 @@ impl DocsContact {
     }
 }
 
+/// The value of a custom field that the trackers configuration declares by name.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CustomValue {
+    Bool(bool),
+    Number(serde_json::Number),
+    Text(String),
+    List(Vec<String>),
+}
+
+/// The custom fields of a ticket by name. A field is `None` if the ticket doesn't set it.
+pub type CustomFields = BTreeMap<String, Option<CustomValue>>;
+
+impl CustomValue {
+    /// The values that a filter compares: each item of a list, or the single value.
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Self::List(items) => items.clone(),
+            other => vec![other.to_string()],
+        }
+    }
+
+    /// Convert a field value from the tracker API, such as a Jira option or a list of users.
+    fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(boolean) => Some(Self::Bool(*boolean)),
+            Value::Number(number) => Some(Self::Number(number.clone())),
+            Value::Array(items) => Some(Self::List(items.iter().filter_map(json_text).collect())),
+            Value::String(_) | Value::Object(_) => json_text(value).map(Self::Text),
+        }
+    }
+}
+
+impl fmt::Display for CustomValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bool(boolean) => write!(f, "{boolean}"),
+            Self::Number(number) => write!(f, "{number}"),
+            Self::Text(text) => write!(f, "{text}"),
+            Self::List(items) => write!(f, "{}", items.join(", ")),
+        }
+    }
+}
+
+/// Represent a field value from the tracker API as text.
+///
+/// Jira stores options, users, and versions as objects. Their readable text
+/// is in the `value`, `name`, `displayName`, or `emailAddress` entry.
+/// An object with a single entry, such as the Bugzilla pool, takes the text of the entry.
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(string) => Some(string.clone()),
+        Value::Object(object) => ["value", "name", "displayName", "emailAddress"]
+            .iter()
+            .find_map(|key| object.get(*key))
+            .or_else(|| {
+                if object.len() == 1 {
+                    object.values().next()
+                } else {
+                    None
+                }
+            })
+            .map_or_else(|| Some(value.to_string()), json_text),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Look up the custom fields in the extra fields of a Bugzilla bug or a Jira issue.
+/// Each field takes the value of the first configured ID that is set.
+fn json_custom_fields(extra: &Value, config: &impl tracker::FieldsConfig) -> CustomFields {
+    config
+        .custom()
+        .iter()
+        .map(|(name, ids)| {
+            let value = ids
+                .iter()
+                .find_map(|id| extra.get(id).and_then(CustomValue::from_json));
+            (name.clone(), value)
+        })
+        .collect()
+}
+
 /// All the extra fields, so that we can implement a standardized
 /// user display string on them.
 #[derive(Clone, Copy)]
@@ -115,6 +260,7 @@ enum Field {
     Subsystems,
     DocTextStatus,
     DocsContact,
+    Custom,
 }
 
 impl fmt::Display for Field {
@@ -126,6 +272,7 @@ impl fmt::Display for Field {
             Self::Subsystems => write!(f, "subsystems"),
             Self::DocTextStatus => write!(f, "doc text status"),
             Self::DocsContact => write!(f, "docs contact"),
+            Self::Custom => write!(f, "custom"),
         }
     }
 }
@@ -143,6 +290,8 @@ pub trait ExtraFields {
     fn doc_text_status(&self, config: &impl tracker::FieldsConfig) -> Result<DocTextStatus>;
     /// Extract the docs contact from the ticket.
     fn docs_contact(&self, config: &impl tracker::FieldsConfig) -> DocsContact;
+    /// Extract the custom fields that the configuration declares by name from the ticket.
+    fn custom_fields(&self, config: &impl tracker::FieldsConfig) -> CustomFields;
     /// Construct a URL back to the original ticket online.
     fn url(&self, tracker: &impl tracker::FieldsConfig) -> String;
 }
@@ -183,10 +332,10 @@ fn extract_field(field_name: Field, extra: &Value, fields: &[String], id: Id) ->
 
             if let Some(string) = try_string {
                 return Ok(string);
-            } else {
-                let error = eyre!("Field `{field}` is not a string: {value:?}");
-                errors.push(error);
             }
+
+            let error = eyre!("Field `{field}` is not a string: {value:?}");
+            errors.push(error);
         } else {
             // The field doesn't exist.
             let error = eyre!("Field `{field}` is missing.");
@@ -206,11 +355,12 @@ fn extract_field(field_name: Field, extra: &Value, fields: &[String], id: Id) ->
     }
 }
 
-/// An enum to standardize the error reporting of Bugzilla and Jira tickets.
+/// An enum to standardize the error reporting of Bugzilla, Jira, and forge tickets.
 #[derive(Clone, Copy)]
 enum Id<'a> {
     BZ(i32),
     Jira(&'a str),
+    Forge(&'a str),
 }
 
 impl fmt::Display for Id<'_> {
@@ -218,6 +368,7 @@ impl fmt::Display for Id<'_> {
         match self {
             Self::BZ(id) => write!(f, "bug {id}"),
             Self::Jira(id) => write!(f, "ticket {id}"),
+            Self::Forge(id) => write!(f, "issue {id}"),
         }
     }
 }
@@ -248,9 +399,25 @@ impl ExtraFields for Bug {
         extract_field(Field::DocType, &self.extra, fields, Id::BZ(self.id))
     }
 
+    /// If the doc text is missing or empty, the configuration can fall back on the bug description.
     fn doc_text(&self, config: &impl tracker::FieldsConfig) -> Result<String> {
         let fields = config.doc_text();
-        extract_field(Field::DocText, &self.extra, fields, Id::BZ(self.id))
+        let doc_text = extract_field(Field::DocText, &self.extra, fields, Id::BZ(self.id));
+
+        let is_empty = doc_text
+            .as_ref()
+            .map_or(true, |text| text.trim().is_empty());
+        if is_empty && config.description_fallback() {
+            if let Some(description) = bug_description(self) {
+                log::info!(
+                    "Using the description as the doc text in {}.",
+                    Id::BZ(self.id)
+                );
+                return Ok(description);
+            }
+        }
+
+        doc_text
     }
 
     fn target_releases(&self, config: &impl tracker::FieldsConfig) -> Vec<String> {
@@ -345,7 +512,7 @@ impl ExtraFields for Bug {
 
         for flag in fields {
             if let Some(rdt) = self.get_flag(flag) {
-                match DocTextStatus::try_from(rdt) {
+                match DocTextStatus::from_value(rdt, config, Id::BZ(self.id)) {
                     Ok(status) => {
                         return Ok(status);
                     }
@@ -403,11 +570,48 @@ impl ExtraFields for Bug {
         DocsContact(self.docs_contact.clone())
     }
 
+    /// A custom field is either a field in the bug, or a flag.
+    fn custom_fields(&self, config: &impl tracker::FieldsConfig) -> CustomFields {
+        let mut fields = json_custom_fields(&self.extra, config);
+
+        for (name, ids) in config.custom() {
+            if fields.get(name).map_or(true, Option::is_none) {
+                let flag = ids.iter().find_map(|id| {
+                    self.flags
+                        .iter()
+                        .flatten()
+                        .find(|flag| &flag.name == id)
+                        .map(|flag| CustomValue::Text(flag.status.clone()))
+                });
+                fields.insert(name.clone(), flag);
+            }
+        }
+
+        fields
+    }
+
     fn url(&self, tracker: &impl tracker::FieldsConfig) -> String {
         format!("{}/show_bug.cgi?id={}", tracker.host(), self.id)
     }
 }
 
+/// Find the description of the bug, which is the text of comment #0.
+///
+/// Bugzilla only reports the comments if the request includes them.
+pub fn bug_description(bug: &Bug) -> Option<String> {
+    let comments = bug.extra.get("comments")?.as_array()?;
+    let first = comments
+        .iter()
+        .find(|comment| comment.get("count").and_then(Value::as_i64) == Some(0))
+        .or_else(|| comments.first())?;
+
+    first
+        .get("text")
+        .and_then(Value::as_str)
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| text.replace('\r', ""))
+}
+
 /// A simple text entry field that might occur at various places in Jira.
 #[derive(Deserialize, Debug)]
 struct TextEntry {
@@ -441,15 +645,32 @@ impl ExtraFields for Issue {
                 }
             } else {
                 errors.push(eyre!("The `{field}` field is missing."));
-            };
+            }
         }
 
         let report = error_chain(errors, Field::DocType, fields, Id::Jira(&self.key));
         Err(report)
     }
 
+    /// Jira Cloud stores the doc text as an ADF document, and Jira Server as text
+    /// that can contain the wiki markup. Convert either of them to AsciiDoc,
+    /// unless the configuration disables the wiki markup.
     fn doc_text(&self, config: &impl tracker::FieldsConfig) -> Result<String> {
         let fields = config.doc_text();
+
+        for field in fields {
+            match self.fields.extra.get(field) {
+                Some(document) if jira_markup::is_adf(document) => {
+                    return Ok(jira_markup::from_adf(document));
+                }
+                Some(Value::String(text)) if config.wiki_markup() => {
+                    return Ok(jira_markup::from_wiki(text));
+                }
+                _ => {}
+            }
+        }
+
+        // No field contains the doc text. Report the problems, or accept an empty field.
         extract_field(
             Field::DocText,
             &self.fields.extra,
@@ -494,7 +715,7 @@ impl ExtraFields for Issue {
                 let string = extract_field(
                     Field::TargetRelease,
                     &self.extra,
-                    &[field.clone()],
+                    std::slice::from_ref(field),
                     Id::Jira(&self.key),
                 );
                 match string {
@@ -596,10 +817,10 @@ impl ExtraFields for Issue {
                     }
                     // If the field is set (Some value), use the regular string parsing.
                     Some(string) => {
-                        return DocTextStatus::try_from(string);
+                        return DocTextStatus::from_value(string, config, Id::Jira(&self.key));
                     }
                 }
-            };
+            }
         }
 
         // No field produced a `Some` value.
@@ -615,6 +836,11 @@ impl ExtraFields for Issue {
     fn docs_contact(&self, config: &impl tracker::FieldsConfig) -> DocsContact {
         let fields = config.docs_contact();
 
+        // The docs contact configuration is optional.
+        if fields.is_empty() {
+            return DocsContact(None);
+        }
+
         for field in fields {
             let contact = self
                 .fields
@@ -637,7 +863,243 @@ impl ExtraFields for Issue {
         DocsContact(None)
     }
 
+    fn custom_fields(&self, config: &impl tracker::FieldsConfig) -> CustomFields {
+        json_custom_fields(&self.fields.extra, config)
+    }
+
     fn url(&self, tracker: &impl tracker::FieldsConfig) -> String {
         format!("{}/browse/{}", tracker.host(), &self.key)
     }
 }
+
+/// A place in a GitHub or GitLab issue that holds the value of a field.
+enum ForgeSource<'a> {
+    /// All labels that start with this prefix. The value is the rest of the label.
+    Label(&'a str),
+    /// The text under this heading in the issue description.
+    Section(&'a str),
+}
+
+impl<'a> TryFrom<&'a str> for ForgeSource<'a> {
+    type Error = color_eyre::eyre::Error;
+
+    fn try_from(field: &'a str) -> Result<Self> {
+        if let Some(prefix) = field.strip_prefix("label:") {
+            Ok(Self::Label(prefix))
+        } else if let Some(heading) = field.strip_prefix("section:") {
+            Ok(Self::Section(heading.trim()))
+        } else {
+            bail!(
+                "Unsupported field `{}`. Use `label:PREFIX` or `section:HEADING`.",
+                field
+            )
+        }
+    }
+}
+
+/// Find the text under this Markdown heading, until the next heading of the same or higher level.
+/// The heading comparison is case-insensitive. Ignores headings inside fenced code blocks.
+fn markdown_section(body: &str, heading: &str) -> Option<String> {
+    // The level of the matching heading, once found.
+    let mut level = None;
+    let mut content = Vec::new();
+    let mut in_code_block = false;
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        let title = &trimmed[hashes..];
+        let is_heading = !in_code_block
+            && (1..=6).contains(&hashes)
+            && (title.is_empty() || title.starts_with(' '));
+
+        match level {
+            Some(level) if is_heading && hashes <= level => break,
+            Some(_) => content.push(line),
+            None if is_heading && title.trim().eq_ignore_ascii_case(heading) => {
+                level = Some(hashes);
+            }
+            None => {}
+        }
+    }
+
+    level.map(|_| content.join("\n").trim().to_string())
+}
+
+/// Collect all values of a field from all its configured sources in a forge issue, in order.
+fn forge_values(
+    field_name: Field,
+    issue: &ForgeIssue,
+    fields: &[String],
+    errors: &mut Vec<Report>,
+) -> Vec<String> {
+    let mut values = Vec::new();
+
+    for field in fields {
+        match ForgeSource::try_from(field.as_str()) {
+            Ok(ForgeSource::Label(prefix)) => {
+                let mut labels: Vec<String> = issue
+                    .labels
+                    .iter()
+                    .filter_map(|label| label.strip_prefix(prefix))
+                    .map(|value| value.trim().to_string())
+                    .collect();
+                if labels.is_empty() {
+                    errors.push(eyre!("No label starts with `{prefix}`."));
+                }
+                values.append(&mut labels);
+            }
+            Ok(ForgeSource::Section(heading)) => {
+                if let Some(text) = markdown_section(&issue.body, heading) {
+                    values.push(text);
+                } else {
+                    errors.push(eyre!("The description has no `{heading}` section."));
+                }
+            }
+            Err(error) => {
+                errors.push(error.wrap_err(format!("Invalid {field_name} field.")));
+            }
+        }
+    }
+
+    values
+}
+
+/// Extract the first value of a field from its configured sources in a forge issue.
+fn forge_field(field_name: Field, issue: &ForgeIssue, fields: &[String]) -> Result<String> {
+    let mut errors = Vec::new();
+    let values = forge_values(field_name, issue, fields, &mut errors);
+
+    values
+        .into_iter()
+        .next()
+        .ok_or_else(|| error_chain(errors, field_name, fields, Id::Forge(&issue.key())))
+}
+
+impl ExtraFields for ForgeIssue {
+    fn doc_type(&self, config: &impl tracker::FieldsConfig) -> Result<String> {
+        forge_field(Field::DocType, self, config.doc_type())
+    }
+
+    /// If the issue has no doc text, treat it only as a warning and use an empty doc text,
+    /// the same way as an empty doc text field in Bugzilla.
+    fn doc_text(&self, config: &impl tracker::FieldsConfig) -> Result<String> {
+        match forge_field(Field::DocText, self, config.doc_text()) {
+            Ok(text) => Ok(text),
+            Err(report) => {
+                log::warn!("{report}");
+                Ok(String::new())
+            }
+        }
+    }
+
+    fn target_releases(&self, config: &impl tracker::FieldsConfig) -> Vec<String> {
+        let fields = config.target_release();
+        let mut errors = Vec::new();
+        let releases = forge_values(Field::TargetRelease, self, fields, &mut errors);
+
+        if !releases.is_empty() {
+            return releases;
+        }
+
+        // Fall back on the milestone.
+        if let Some(milestone) = &self.milestone {
+            vec![milestone.clone()]
+        } else {
+            if !fields.is_empty() {
+                let report =
+                    error_chain(errors, Field::TargetRelease, fields, Id::Forge(&self.key()));
+                log::warn!("{report}");
+            }
+            Vec::new()
+        }
+    }
+
+    fn subsystems(&self, config: &impl tracker::FieldsConfig) -> Result<Vec<String>> {
+        let fields = config.subsystems();
+        let mut errors = Vec::new();
+
+        // The subsystems configuration is optional and can be left empty.
+        // If a ticket actually requests organization by subsystems, the following error appears.
+        if fields.is_empty() {
+            let error = eyre!("No subsystems field is configured in the trackers.yaml file.");
+            errors.push(error);
+        }
+
+        let subsystems = forge_values(Field::Subsystems, self, fields, &mut errors);
+
+        if subsystems.is_empty() {
+            Err(error_chain(
+                errors,
+                Field::Subsystems,
+                fields,
+                Id::Forge(&self.key()),
+            ))
+        } else {
+            Ok(subsystems)
+        }
+    }
+
+    /// If the issue has no doc text status, treat it only as a warning
+    /// and proceed with the default value, the same way as an unset flag in Bugzilla.
+    fn doc_text_status(&self, config: &impl tracker::FieldsConfig) -> Result<DocTextStatus> {
+        match forge_field(Field::DocTextStatus, self, config.doc_text_status()) {
+            Ok(status) => DocTextStatus::from_value(&status, config, Id::Forge(&self.key())),
+            Err(report) => {
+                log::warn!("{report}");
+                Ok(DocTextStatus::InProgress)
+            }
+        }
+    }
+
+    fn docs_contact(&self, config: &impl tracker::FieldsConfig) -> DocsContact {
+        let fields = config.docs_contact();
+
+        match forge_field(Field::DocsContact, self, fields) {
+            Ok(contact) => DocsContact(Some(contact)),
+            Err(report) => {
+                // This field is non-critical.
+                if !fields.is_empty() {
+                    log::warn!("{report}");
+                }
+                DocsContact(None)
+            }
+        }
+    }
+
+    /// A custom field has a single value, or a list if its sources hold several values,
+    /// such as several labels with the same prefix.
+    fn custom_fields(&self, config: &impl tracker::FieldsConfig) -> CustomFields {
+        config
+            .custom()
+            .iter()
+            .map(|(name, sources)| {
+                let mut errors = Vec::new();
+                let mut values = forge_values(Field::Custom, self, sources, &mut errors);
+                // A ticket doesn't have to set a custom field, but the configured sources must be valid.
+                for source in sources {
+                    if let Err(error) = ForgeSource::try_from(source.as_str()) {
+                        log::warn!("Invalid `{name}` custom field: {error}");
+                    }
+                }
+
+                let value = match values.len() {
+                    0 => None,
+                    1 => values.pop().map(CustomValue::Text),
+                    _ => Some(CustomValue::List(values)),
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// The forges report the URL to the issue, so the configured host isn't necessary.
+    fn url(&self, _tracker: &impl tracker::FieldsConfig) -> String {
+        self.url.clone()
+    }
+}
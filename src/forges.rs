@@ -0,0 +1,699 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+Access to the issues on GitHub and GitLab, the Git forges.
+
+Unlike Bugzilla and Jira, the forges have no library that would download their issues,
+so this module implements a minimal client of their REST APIs.
+
+The tickets configuration identifies a forge issue by a key such as `owner/repo#123`.
+The search string differs between the forges:
+
+* On GitHub, it's a query in the GitHub search syntax, such as `repo:owner/repo label:release-note`.
+  The search only returns issues, not pull requests.
+* On GitLab, it's a project path, optionally followed by the URL-encoded parameters
+  of the GitLab issues API, such as `group/project?labels=release%20note&state=closed`.
+*/
+
+use std::collections::HashMap;
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+
+use crate::config::tracker;
+use crate::tracker_access::{GitHub, GitLab, Tracker};
+
+/// The number of issues to request on a single page of results.
+const PAGE_SIZE: usize = 100;
+
+/// The maximum number of issues to request by their keys at the same time.
+/// The forges limit the rate of requests, and reject a burst of too many of them.
+const CONCURRENT_REQUESTS: usize = 8;
+
+/// The user agent that identifies this program to the forge. GitHub rejects requests without it.
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// An issue from GitHub or GitLab, in a common format for both forges.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForgeIssue {
+    pub service: tracker::Service,
+    /// The path to the repository, such as `owner/repo` or `group/subgroup/project`.
+    pub project: String,
+    /// The number of the issue within the repository.
+    pub number: u64,
+    pub title: String,
+    /// The Markdown description of the issue.
+    pub body: String,
+    /// The state, such as `open` or `closed`.
+    pub state: String,
+    pub labels: Vec<String>,
+    pub assignee: Option<String>,
+    pub milestone: Option<String>,
+    /// The URL to the issue in the web UI.
+    pub url: String,
+    /// A confidential issue in GitLab, or any issue in a private GitHub repository.
+    pub confidential: bool,
+}
+
+impl ForgeIssue {
+    /// The key of the issue, in the same format as in the tickets configuration.
+    pub fn key(&self) -> String {
+        format!("{}#{}", self.project, self.number)
+    }
+
+    pub fn is_open(&self) -> bool {
+        // GitHub reports `open` and GitLab reports `opened`.
+        self.state.starts_with("open")
+    }
+}
+
+/// A user as GitHub reports it.
+#[derive(Deserialize)]
+struct GitHubUser {
+    login: String,
+}
+
+/// A label as GitHub reports it.
+#[derive(Deserialize)]
+struct GitHubLabel {
+    name: String,
+}
+
+/// A milestone as GitHub or GitLab reports it.
+#[derive(Deserialize)]
+struct Milestone {
+    title: String,
+}
+
+/// An issue as GitHub reports it.
+#[derive(Deserialize)]
+struct GitHubIssue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    state: String,
+    labels: Vec<GitHubLabel>,
+    assignee: Option<GitHubUser>,
+    milestone: Option<Milestone>,
+    html_url: String,
+    /// The API URL of the repository, which ends with `owner/repo`.
+    repository_url: String,
+}
+
+impl GitHubIssue {
+    fn into_forge_issue(self) -> ForgeIssue {
+        let mut segments = self.repository_url.rsplit('/');
+        let repo = segments.next().unwrap_or_default();
+        let owner = segments.next().unwrap_or_default();
+
+        ForgeIssue {
+            service: GitHub::SERVICE,
+            project: format!("{owner}/{repo}"),
+            number: self.number,
+            title: self.title,
+            body: self.body.unwrap_or_default(),
+            state: self.state,
+            labels: self.labels.into_iter().map(|label| label.name).collect(),
+            assignee: self.assignee.map(|user| user.login),
+            milestone: self.milestone.map(|milestone| milestone.title),
+            url: self.html_url,
+            // GitHub issues have no visibility of their own. The repository sets it later.
+            confidential: true,
+        }
+    }
+}
+
+/// A repository as GitHub reports it.
+#[derive(Deserialize)]
+struct GitHubRepository {
+    private: bool,
+}
+
+/// The results of a GitHub search.
+#[derive(Deserialize)]
+struct GitHubSearch {
+    total_count: usize,
+    items: Vec<GitHubIssue>,
+}
+
+/// A user as GitLab reports it.
+#[derive(Deserialize)]
+struct GitLabUser {
+    username: String,
+}
+
+/// The references to an issue as GitLab reports them.
+#[derive(Deserialize)]
+struct GitLabReferences {
+    /// The complete reference, such as `group/project#123`.
+    full: String,
+}
+
+/// An issue as GitLab reports it.
+#[derive(Deserialize)]
+struct GitLabIssue {
+    iid: u64,
+    title: String,
+    description: Option<String>,
+    state: String,
+    labels: Vec<String>,
+    assignee: Option<GitLabUser>,
+    milestone: Option<Milestone>,
+    web_url: String,
+    #[serde(default)]
+    confidential: bool,
+    references: GitLabReferences,
+}
+
+impl GitLabIssue {
+    fn into_forge_issue(self) -> ForgeIssue {
+        let project = self
+            .references
+            .full
+            .rsplit_once('#')
+            .map_or_else(String::new, |(project, _number)| project.to_string());
+
+        ForgeIssue {
+            service: GitLab::SERVICE,
+            project,
+            number: self.iid,
+            title: self.title,
+            body: self.description.unwrap_or_default(),
+            state: self.state,
+            labels: self.labels,
+            assignee: self.assignee.map(|user| user.username),
+            milestone: self.milestone.map(|milestone| milestone.title),
+            url: self.web_url,
+            confidential: self.confidential,
+        }
+    }
+}
+
+/// Split an issue key such as `owner/repo#123` to the project path and the issue number.
+fn parse_key(key: &str) -> Result<(&str, u64)> {
+    let (project, number) = key.rsplit_once('#').ok_or_else(|| {
+        eyre!(
+            "Invalid issue key {:?}. Use the `owner/repo#123` format.",
+            key
+        )
+    })?;
+    let number = number
+        .parse()
+        .wrap_err_with(|| format!("Invalid issue number in key {key:?}."))?;
+
+    Ok((project, number))
+}
+
+/// Encode the project path as a single URL path segment, which the GitLab API requires.
+fn encode_project(project: &str) -> String {
+    project.replace('/', "%2F")
+}
+
+/// A client that accesses the REST API of GitHub or GitLab.
+#[derive(Clone)]
+pub struct ForgeClient {
+    service: tracker::Service,
+    /// The root URL of the REST API, such as `https://api.github.com` or `https://gitlab.com/api/v4`.
+    host: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl ForgeClient {
+    /// Prepare a client to the forge API at this URL.
+    /// Without a token, the client can only access public repositories.
+    pub fn at(service: tracker::Service, host: &str, token: Option<String>) -> Result<Self> {
+        if service != GitHub::SERVICE && service != GitLab::SERVICE {
+            bail!("{} is not a Git forge.", service);
+        }
+
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .wrap_err("Failed to prepare the HTTP client.")?;
+
+        Ok(Self {
+            service,
+            host: host.trim_end_matches('/').to_string(),
+            token,
+            client,
+        })
+    }
+
+    /// Send a GET request to the API and parse the JSON response.
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let url = format!("{}{}", self.host, path);
+        let mut request = self.client.get(&url).query(query);
+
+        if let Some(token) = &self.token {
+            request = if self.service == GitLab::SERVICE {
+                request.header("PRIVATE-TOKEN", token)
+            } else {
+                request.bearer_auth(token)
+            };
+        }
+
+        let response = request
+            .send()
+            .await
+            .wrap_err_with(|| format!("Failed to access {url}"))?
+            .error_for_status()
+            .wrap_err_with(|| format!("{} refused the request.", self.service))?;
+
+        response
+            .json()
+            .await
+            .wrap_err_with(|| format!("Failed to parse the response from {url}"))
+    }
+
+    /// Download a single issue by its key.
+    async fn issue(&self, key: &str) -> Result<ForgeIssue> {
+        let (project, number) = parse_key(key)?;
+
+        let issue = if self.service == GitLab::SERVICE {
+            let path = format!("/projects/{}/issues/{number}", encode_project(project));
+            self.get::<GitLabIssue>(&path, &[])
+                .await?
+                .into_forge_issue()
+        } else {
+            let path = format!("/repos/{project}/issues/{number}");
+            self.get::<GitHubIssue>(&path, &[])
+                .await?
+                .into_forge_issue()
+        };
+
+        // Keep the project path exactly as the tickets configuration spells it,
+        // so that the issue matches its query.
+        Ok(ForgeIssue {
+            project: project.to_string(),
+            ..issue
+        })
+    }
+
+    /// Download the issues with these keys. The forges don't support requesting several issues
+    /// by their keys at once, so this function sends a limited number of requests concurrently.
+    pub async fn issues(&self, keys: &[&str]) -> Result<Vec<ForgeIssue>> {
+        let mut issues: Vec<ForgeIssue> = stream::iter(keys)
+            .map(|key| self.issue(key))
+            .buffer_unordered(CONCURRENT_REQUESTS)
+            .try_collect()
+            .await?;
+
+        if self.service == GitHub::SERVICE {
+            self.set_github_visibility(&mut issues).await?;
+        }
+
+        Ok(issues)
+    }
+
+    /// Mark the GitHub issues in private repositories as confidential, and the others as public.
+    /// If the visibility of a repository is unavailable, the download fails
+    /// rather than publish its issues.
+    async fn set_github_visibility(&self, issues: &mut [ForgeIssue]) -> Result<()> {
+        let mut private_repos: HashMap<String, bool> = HashMap::new();
+
+        for issue in issues {
+            let private = if let Some(private) = private_repos.get(&issue.project) {
+                *private
+            } else {
+                let path = format!("/repos/{}", issue.project);
+                let repository: GitHubRepository =
+                    self.get(&path, &[]).await.wrap_err_with(|| {
+                        format!(
+                            "Cannot determine if the {} repository is private.",
+                            issue.project
+                        )
+                    })?;
+                private_repos.insert(issue.project.clone(), repository.private);
+                repository.private
+            };
+
+            issue.confidential = private;
+        }
+
+        Ok(())
+    }
+
+    /// Download all issues that match this search.
+    pub async fn search(&self, search: &str) -> Result<Vec<ForgeIssue>> {
+        if self.service == GitLab::SERVICE {
+            self.search_gitlab(search).await
+        } else {
+            self.search_github(search).await
+        }
+    }
+
+    /// Search GitHub using the GitHub search syntax.
+    async fn search_github(&self, search: &str) -> Result<Vec<ForgeIssue>> {
+        let mut issues = Vec::new();
+
+        // The search API also returns pull requests, unless the search excludes them.
+        let search = if search
+            .split_whitespace()
+            .any(|term| term == "is:issue" || term == "type:issue")
+        {
+            search.to_string()
+        } else {
+            format!("{search} is:issue")
+        };
+
+        for page in 1.. {
+            let query = [
+                ("q", search.clone()),
+                ("per_page", PAGE_SIZE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let results: GitHubSearch = self.get("/search/issues", &query).await?;
+            let last_page = results.items.len() < PAGE_SIZE;
+
+            issues.extend(results.items.into_iter().map(GitHubIssue::into_forge_issue));
+
+            if last_page || issues.len() >= results.total_count {
+                break;
+            }
+        }
+
+        self.set_github_visibility(&mut issues).await?;
+
+        Ok(issues)
+    }
+
+    /// Search the issues in a GitLab project, using the parameters of the GitLab issues API.
+    async fn search_gitlab(&self, search: &str) -> Result<Vec<ForgeIssue>> {
+        let (project, parameters) = search.split_once('?').unwrap_or((search, ""));
+        let path = format!("/projects/{}/issues", encode_project(project));
+
+        // The parameters are URL-encoded in the search. Decode them,
+        // because the request encodes them again.
+        let mut parameters: Vec<(String, String)> = form_urlencoded::parse(parameters.as_bytes())
+            .into_owned()
+            .collect();
+        parameters.push(("per_page".to_string(), PAGE_SIZE.to_string()));
+
+        let mut issues = Vec::new();
+
+        for page in 1.. {
+            let mut query: Vec<(&str, String)> = parameters
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.clone()))
+                .collect();
+            query.push(("page", page.to_string()));
+
+            let results: Vec<GitLabIssue> = self.get(&path, &query).await?;
+            let last_page = results.len() < PAGE_SIZE;
+
+            issues.extend(results.into_iter().map(GitLabIssue::into_forge_issue));
+
+            if last_page {
+                break;
+            }
+        }
+
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    /// An issue in the format of the GitHub API.
+    fn github_issue(number: u64) -> Value {
+        json!({
+            "number": number,
+            "title": format!("Issue {number}"),
+            "body": "The description.",
+            "state": "open",
+            "labels": [{"name": "doc-type/Bug Fix"}],
+            "assignee": {"login": "writer"},
+            "milestone": {"title": "1.0"},
+            "html_url": format!("https://github.com/owner/repo/issues/{number}"),
+            "repository_url": "https://api.github.com/repos/owner/repo",
+        })
+    }
+
+    /// Respond to the requests for the `owner/repo` repository on GitHub.
+    async fn mount_github_repository(server: &MockServer, private: bool) {
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "private": private })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    /// An issue in the format of the GitLab API.
+    fn gitlab_issue(iid: u64) -> Value {
+        json!({
+            "iid": iid,
+            "title": format!("Issue {iid}"),
+            "description": null,
+            "state": "opened",
+            "labels": ["doc-type/Bug Fix"],
+            "assignee": null,
+            "milestone": null,
+            "web_url": format!("https://gitlab.com/group/project/-/issues/{iid}"),
+            "confidential": true,
+            "references": {"full": format!("group/project#{iid}")},
+        })
+    }
+
+    #[tokio::test]
+    async fn github_search_excludes_pull_requests() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search/issues"))
+            .and(query_param("q", "repo:owner/repo label:rn is:issue"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "total_count": 2,
+                "items": [github_issue(1), github_issue(2)],
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        mount_github_repository(&server, false).await;
+
+        let client = ForgeClient::at(GitHub::SERVICE, &server.uri(), None)?;
+        let issues = client.search("repo:owner/repo label:rn").await?;
+
+        let keys: Vec<String> = issues.iter().map(ForgeIssue::key).collect();
+        assert_eq!(keys, ["owner/repo#1", "owner/repo#2"]);
+        assert_eq!(issues[0].labels, ["doc-type/Bug Fix"]);
+        assert_eq!(issues[0].assignee.as_deref(), Some("writer"));
+        assert!(issues[0].is_open());
+        assert!(!issues[0].confidential);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn github_search_keeps_an_explicit_issue_qualifier() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search/issues"))
+            .and(query_param("q", "is:issue repo:owner/repo"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "total_count": 0,
+                "items": [],
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ForgeClient::at(GitHub::SERVICE, &server.uri(), None)?;
+        let issues = client.search("is:issue repo:owner/repo").await?;
+
+        assert!(issues.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn github_issue_by_key() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/issues/7"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(github_issue(7)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        mount_github_repository(&server, false).await;
+
+        let client = ForgeClient::at(GitHub::SERVICE, &server.uri(), Some("secret".to_string()))?;
+        let issues = client.issues(&["owner/repo#7"]).await?;
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key(), "owner/repo#7");
+        assert_eq!(issues[0].service, GitHub::SERVICE);
+        assert_eq!(issues[0].milestone.as_deref(), Some("1.0"));
+        assert!(!issues[0].confidential);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn github_issues_in_a_private_repository_are_confidential() -> Result<()> {
+        let server = MockServer::start().await;
+        for number in 1..=20 {
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/owner/repo/issues/{number}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(github_issue(number)))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        // The repository is requested only once for all its issues.
+        mount_github_repository(&server, true).await;
+
+        let keys: Vec<String> = (1..=20)
+            .map(|number| format!("owner/repo#{number}"))
+            .collect();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+
+        let client = ForgeClient::at(GitHub::SERVICE, &server.uri(), None)?;
+        let issues = client.issues(&keys).await?;
+
+        assert_eq!(issues.len(), 20);
+        assert!(issues.iter().all(|issue| issue.confidential));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn github_unknown_visibility_is_an_error() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/issues/7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(github_issue(7)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let client = ForgeClient::at(GitHub::SERVICE, &server.uri(), None)?;
+
+        assert!(client.issues(&["owner/repo#7"]).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn github_refused_request_is_an_error() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/issues/7"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let client = ForgeClient::at(GitHub::SERVICE, &server.uri(), None)?;
+
+        assert!(client.issues(&["owner/repo#7"]).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn gitlab_search_decodes_the_parameters() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/projects/group%2Fproject/issues"))
+            .and(query_param("labels", "release note,doc/fix"))
+            .and(query_param("state", "closed"))
+            .and(query_param("per_page", "100"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([gitlab_issue(3)])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ForgeClient::at(GitLab::SERVICE, &server.uri(), None)?;
+        let issues = client
+            .search("group/project?labels=release%20note%2Cdoc%2Ffix&state=closed")
+            .await?;
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key(), "group/project#3");
+        assert!(issues[0].confidential);
+        assert!(issues[0].body.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn gitlab_search_reads_all_pages() -> Result<()> {
+        let server = MockServer::start().await;
+        let full_page: Vec<Value> = (1..=100).map(gitlab_issue).collect();
+        Mock::given(method("GET"))
+            .and(path("/projects/group%2Fproject/issues"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(full_page))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/projects/group%2Fproject/issues"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([gitlab_issue(101)])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ForgeClient::at(GitLab::SERVICE, &server.uri(), None)?;
+        let issues = client.search("group/project").await?;
+
+        assert_eq!(issues.len(), 101);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn gitlab_issue_by_key() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/projects/group%2Fproject/issues/3"))
+            .and(header("PRIVATE-TOKEN", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(gitlab_issue(3)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ForgeClient::at(GitLab::SERVICE, &server.uri(), Some("secret".to_string()))?;
+        let issues = client.issues(&["group/project#3"]).await?;
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].service, GitLab::SERVICE);
+        assert_eq!(issues[0].state, "opened");
+        assert!(issues[0].is_open());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,787 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+A conversion of the Jira rich text formats in doc text to AsciiDoc.
+
+Jira stores a rich text field in one of two formats:
+
+* Jira Cloud returns an Atlassian Document Format (ADF) document, which is a JSON tree.
+  This module converts the whole document.
+* Jira Server returns a string in the Jira wiki markup. Release notes in Jira are usually
+  written in AsciiDoc, and the two languages share some of their syntax, such as `*bold*`.
+  This module converts only the wiki constructs that don't mean anything else in AsciiDoc:
+  headings such as `h3.`, `{code}`, `{noformat}`, `{quote}`, `{panel}`, and `{color}` blocks,
+  `bq.` quotes, `#` numbered lists, tables, `{{monospace}}`, `??citations??`,
+  `[text|url]` links, and `{anchor}` macros.
+
+A release note can't contain sections, so headings become block titles, such as `.Title`.
+*/
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+/// A heading, such as `h3. Title`.
+static WIKI_HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^h[1-6]\.\s+(.*)$").expect("Invalid wiki heading regex."));
+
+/// A numbered or mixed list item, such as `# Item` or `*# Item`.
+static WIKI_NUMBERED_ITEM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([*#]*#)\s+(.*)$").expect("Invalid wiki list regex."));
+
+/// A single-line quote, such as `bq. Text`.
+static WIKI_QUOTE_LINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^bq\.\s+(.*)$").expect("Invalid wiki quote regex."));
+
+/// The opening or closing tag of a block macro, such as `{code:java}`, with its parameters.
+static WIKI_BLOCK_TAG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{(code|noformat|quote|panel)(?::([^}]*))?\}").expect("Invalid wiki block regex.")
+});
+
+/// A link, such as `[text|https://example.com]` or `[https://example.com]`.
+static WIKI_LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\[(?:([^\[\]|]*)\|)?([^\[\]|\s]+)\]").expect("Invalid wiki link regex.")
+});
+
+/// Monospace text, such as `{{text}}`.
+static WIKI_MONOSPACE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{(.+?)\}\}").expect("Invalid wiki monospace regex."));
+
+/// A citation, such as `??text??`.
+static WIKI_CITATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\?\?(\S(?:.*?\S)?)\?\?").expect("Invalid wiki citation regex."));
+
+/// A colored span, such as `{color:red}text{color}`, which AsciiDoc doesn't need.
+static WIKI_COLOR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{color(?::[^}]*)?\}").expect("Invalid wiki color regex."));
+
+/// An anchor, such as `{anchor:name}`.
+static WIKI_ANCHOR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{anchor:([^}]+)\}").expect("Invalid wiki anchor regex."));
+
+/// Check if the field value is an ADF document rather than text.
+pub fn is_adf(value: &Value) -> bool {
+    value.get("type").and_then(Value::as_str) == Some("doc")
+}
+
+/// Convert an ADF document to AsciiDoc.
+pub fn from_adf(document: &Value) -> String {
+    joined_blocks(children(document), 1)
+}
+
+/// The child nodes of an ADF node.
+fn children(node: &Value) -> &[Value] {
+    node.get("content")
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+/// A string attribute of an ADF node.
+fn attribute<'a>(node: &'a Value, name: &str) -> Option<&'a str> {
+    node.get("attrs")
+        .and_then(|attrs| attrs.get(name))
+        .and_then(Value::as_str)
+}
+
+/// Convert the block nodes to AsciiDoc blocks. The depth is the nesting level of lists.
+fn blocks(nodes: &[Value], depth: usize) -> Vec<String> {
+    nodes
+        .iter()
+        .filter_map(|node| block(node, depth))
+        .filter(|block| !block.is_empty())
+        .collect()
+}
+
+/// Convert the block nodes and separate the blocks with empty lines.
+/// A heading becomes the title of the following block, so it stays directly above the block.
+fn joined_blocks(nodes: &[Value], depth: usize) -> String {
+    let mut text = String::new();
+    let mut after_heading = false;
+
+    for node in nodes {
+        let Some(converted) = block(node, depth).filter(|block| !block.is_empty()) else {
+            continue;
+        };
+        if !text.is_empty() {
+            text.push_str(if after_heading { "\n" } else { "\n\n" });
+        }
+        text.push_str(&converted);
+        after_heading = node.get("type").and_then(Value::as_str) == Some("heading");
+    }
+
+    text
+}
+
+/// Convert a single block node to an AsciiDoc block, or nothing if it has no text equivalent.
+fn block(node: &Value, depth: usize) -> Option<String> {
+    let node_type = node.get("type").and_then(Value::as_str).unwrap_or_default();
+
+    let converted = match node_type {
+        "paragraph" => inlines(children(node)),
+        "heading" => format!(".{}", inlines(children(node))),
+        "bulletList" => list(node, "*", depth),
+        "orderedList" => list(node, ".", depth),
+        "codeBlock" => {
+            let code = inlines_plain(children(node));
+            match attribute(node, "language") {
+                Some(language) if !language.is_empty() => {
+                    format!("[source,{language}]\n----\n{code}\n----")
+                }
+                _ => format!("----\n{code}\n----"),
+            }
+        }
+        "blockquote" => format!("____\n{}\n____", joined_blocks(children(node), 1)),
+        "panel" => {
+            let label = match attribute(node, "panelType") {
+                Some("warning") => "WARNING",
+                Some("error") => "CAUTION",
+                Some("success") => "TIP",
+                _ => "NOTE",
+            };
+            format!(
+                "[{label}]\n====\n{}\n====",
+                joined_blocks(children(node), 1)
+            )
+        }
+        "expand" | "nestedExpand" => {
+            let title = attribute(node, "title")
+                .filter(|title| !title.is_empty())
+                .map(|title| format!(".{title}\n"))
+                .unwrap_or_default();
+            format!(
+                "[%collapsible]\n{title}====\n{}\n====",
+                joined_blocks(children(node), 1)
+            )
+        }
+        "rule" => "'''".to_string(),
+        "table" => table(node),
+        // Media only refer to files attached to the ticket, which the document can't show.
+        "mediaSingle" | "mediaGroup" | "media" => return None,
+        _ => {
+            let content = children(node);
+            if content.is_empty() {
+                return None;
+            }
+            joined_blocks(content, depth)
+        }
+    };
+
+    Some(converted)
+}
+
+/// Convert a list. The items of a nested list repeat the marker once more for each level.
+fn list(node: &Value, marker: &str, depth: usize) -> String {
+    let marker = marker.repeat(depth);
+
+    children(node)
+        .iter()
+        .map(|item| {
+            let mut lines = Vec::new();
+            let mut first = true;
+
+            for child in children(item) {
+                let child_type = child.get("type").and_then(Value::as_str);
+                let nested = matches!(child_type, Some("bulletList" | "orderedList"));
+
+                if let Some(converted) = block(child, depth + 1) {
+                    if first && !nested {
+                        lines.push(format!("{marker} {converted}"));
+                    } else if nested {
+                        lines.push(converted);
+                    } else {
+                        // Attach any further block to the list item.
+                        lines.push(format!("+\n{converted}"));
+                    }
+                    first = false;
+                }
+            }
+
+            if lines.is_empty() {
+                format!("{marker} ")
+            } else {
+                lines.join("\n")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Convert a table. The first row is the header if it consists of header cells.
+fn table(node: &Value) -> String {
+    let rows = children(node);
+    let has_header = rows.first().is_some_and(|row| {
+        children(row)
+            .iter()
+            .all(|cell| cell.get("type").and_then(Value::as_str) == Some("tableHeader"))
+    });
+
+    let rows: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            children(row)
+                .iter()
+                .map(|cell| format!("|{}", blocks(children(cell), 1).join(" ")))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+
+    let options = if has_header {
+        "[options=\"header\"]\n"
+    } else {
+        ""
+    };
+
+    format!("{options}|===\n{}\n|===", rows.join("\n"))
+}
+
+/// Convert the inline nodes, such as text with marks, to AsciiDoc text.
+fn inlines(nodes: &[Value]) -> String {
+    nodes.iter().map(inline).collect()
+}
+
+/// Join the text of the inline nodes without any formatting, such as in a code block.
+fn inlines_plain(nodes: &[Value]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node.get("type").and_then(Value::as_str) {
+            Some("hardBreak") => "\n",
+            _ => node.get("text").and_then(Value::as_str).unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Convert a single inline node to AsciiDoc text.
+fn inline(node: &Value) -> String {
+    match node.get("type").and_then(Value::as_str).unwrap_or_default() {
+        "text" => {
+            let text = node.get("text").and_then(Value::as_str).unwrap_or_default();
+            let marks = node
+                .get("marks")
+                .and_then(Value::as_array)
+                .map_or(&[][..], Vec::as_slice);
+            marked_text(text, marks)
+        }
+        "hardBreak" => " +\n".to_string(),
+        "mention" | "emoji" | "status" => attribute(node, "text")
+            .or_else(|| attribute(node, "shortName"))
+            .unwrap_or_default()
+            .to_string(),
+        "inlineCard" => attribute(node, "url")
+            .map(|url| format!("{url}[]"))
+            .unwrap_or_default(),
+        "date" => attribute(node, "timestamp")
+            .and_then(|timestamp| timestamp.parse::<i64>().ok())
+            .and_then(|millis| time::OffsetDateTime::from_unix_timestamp(millis / 1000).ok())
+            .map(|date| date.date().to_string())
+            .unwrap_or_default(),
+        _ => inlines(children(node)),
+    }
+}
+
+/// Apply the ADF marks, such as bold or a link, to the text.
+fn marked_text(text: &str, marks: &[Value]) -> String {
+    // Formatting can't start or end with a space, so keep the spaces outside of the marks.
+    let content = text.trim();
+    if content.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+
+    let mut result = content.to_string();
+    let mut link = None;
+
+    for mark in marks {
+        result = match mark.get("type").and_then(Value::as_str).unwrap_or_default() {
+            "code" => format!("`+{result}+`"),
+            "strong" => format!("**{result}**"),
+            "em" => format!("__{result}__"),
+            "strike" => format!("[.line-through]#{result}#"),
+            "underline" => format!("[.underline]#{result}#"),
+            "subsup" => match attribute(mark, "type") {
+                Some("sub") => format!("~{result}~"),
+                _ => format!("^{result}^"),
+            },
+            "link" => {
+                link = attribute(mark, "href");
+                result
+            }
+            _ => result,
+        };
+    }
+
+    // The link wraps all other formatting.
+    if let Some(href) = link {
+        result = if content == href {
+            format!("{href}[]")
+        } else if href.contains("://") || href.starts_with("mailto:") {
+            format!("{href}[{result}]")
+        } else {
+            format!("link:{href}[{result}]")
+        };
+    }
+
+    format!("{leading}{result}{trailing}")
+}
+
+/// Convert the Jira wiki constructs in the text to AsciiDoc, and keep the rest of the text.
+pub fn from_wiki(text: &str) -> String {
+    let mut output: Vec<String> = Vec::new();
+    // The AsciiDoc delimiter of the code block that's currently open, if any.
+    let mut code_block: Option<&str> = None;
+    // The rows of the wiki table that's currently open.
+    let mut table: Vec<String> = Vec::new();
+    let mut table_header = false;
+    // An AsciiDoc table in the text is already converted.
+    let mut in_asciidoc_table = false;
+    // The content of an AsciiDoc listing or literal block in the text stays as it is.
+    let mut asciidoc_block: Option<&str> = None;
+    // Quotes and panels use the same tag to open and to close the block.
+    let mut in_quote = false;
+    let mut in_panel = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_end();
+
+        if let Some(delimiter) = asciidoc_block {
+            if trimmed == delimiter {
+                asciidoc_block = None;
+            }
+            output.push(line.to_string());
+            continue;
+        }
+        if trimmed == "----" || trimmed == "...." {
+            asciidoc_block = Some(if trimmed == "----" { "----" } else { "...." });
+            output.push(line.to_string());
+            continue;
+        }
+
+        if let Some(delimiter) = code_block {
+            if let Some(position) = closing_tag(trimmed, delimiter) {
+                if position > 0 {
+                    output.push(trimmed[..position].to_string());
+                }
+                output.push(delimiter.to_string());
+                code_block = None;
+            } else {
+                output.push(line.to_string());
+            }
+            continue;
+        }
+
+        // A wiki table is a sequence of lines that start with `|`.
+        if trimmed.starts_with("|===") {
+            in_asciidoc_table = !in_asciidoc_table;
+        } else if !in_asciidoc_table && trimmed.starts_with('|') {
+            if table.is_empty() {
+                table_header = trimmed.starts_with("||");
+                separate_block(&mut output);
+            }
+            table.push(wiki_table_row(&wiki_inline(trimmed)));
+            continue;
+        }
+        if !table.is_empty() {
+            output.push(finish_table(&mut table, table_header));
+        }
+
+        if let Some(caps) = WIKI_BLOCK_TAG
+            .captures(trimmed)
+            .filter(|caps| caps.get(0).map(|tag| tag.start()) == Some(0))
+        {
+            let tag = caps.get(0).map_or(0, |tag| tag.end());
+            let rest = &trimmed[tag..];
+            match &caps[1] {
+                "code" => {
+                    let language = caps
+                        .get(2)
+                        .and_then(|params| code_language(params.as_str()));
+                    code_block = open_code_block(&mut output, "----", language, rest);
+                }
+                "noformat" => code_block = open_code_block(&mut output, "....", None, rest),
+                "quote" => {
+                    in_quote = !in_quote;
+                    if in_quote {
+                        separate_block(&mut output);
+                    }
+                    output.push(format!("____{}", wiki_rest(rest)));
+                }
+                _ => {
+                    in_panel = !in_panel;
+                    if in_panel {
+                        separate_block(&mut output);
+                        let title = caps
+                            .get(2)
+                            .and_then(|params| parameter(params.as_str(), "title"));
+                        if let Some(title) = title {
+                            output.push(format!(".{title}"));
+                        }
+                    }
+                    output.push(format!("===={}", wiki_rest(rest)));
+                }
+            }
+            continue;
+        }
+
+        let converted = if let Some(caps) = WIKI_HEADING.captures(trimmed) {
+            format!(".{}", wiki_inline(&caps[1]))
+        } else if let Some(caps) = WIKI_QUOTE_LINE.captures(trimmed) {
+            separate_block(&mut output);
+            format!("____\n{}\n____", wiki_inline(&caps[1]))
+        } else if let Some(caps) = WIKI_NUMBERED_ITEM.captures(trimmed) {
+            format!("{} {}", ".".repeat(caps[1].len()), wiki_inline(&caps[2]))
+        } else {
+            wiki_inline(line)
+        };
+
+        output.push(converted);
+    }
+
+    if !table.is_empty() {
+        output.push(finish_table(&mut table, table_header));
+    }
+
+    output.join("\n")
+}
+
+/// Open an AsciiDoc code block with this delimiter. The code can continue on the same line
+/// as the opening tag, and even end there.
+///
+/// Returns the delimiter if the block stays open on the following lines.
+fn open_code_block(
+    output: &mut Vec<String>,
+    delimiter: &'static str,
+    language: Option<&str>,
+    rest: &str,
+) -> Option<&'static str> {
+    separate_block(output);
+    if let Some(language) = language {
+        output.push(format!("[source,{language}]"));
+    }
+    output.push(delimiter.to_string());
+
+    if let Some(position) = closing_tag(rest, delimiter) {
+        output.push(rest[..position].to_string());
+        output.push(delimiter.to_string());
+        None
+    } else {
+        if !rest.is_empty() {
+            output.push(rest.to_string());
+        }
+        Some(delimiter)
+    }
+}
+
+/// Start a new block on a new paragraph, unless it follows a block title.
+fn separate_block(output: &mut Vec<String>) {
+    if let Some(previous) = output.last() {
+        let is_title = previous.starts_with('.')
+            && previous
+                .chars()
+                .nth(1)
+                .is_some_and(|c| c != '.' && !c.is_whitespace());
+        if !previous.is_empty() && !is_title {
+            output.push(String::new());
+        }
+    }
+}
+
+/// Find the position of the tag that closes the code block on this line.
+fn closing_tag(line: &str, delimiter: &str) -> Option<usize> {
+    let tag = if delimiter == "----" {
+        "{code}"
+    } else {
+        "{noformat}"
+    };
+    line.find(tag)
+}
+
+/// Continue the text that follows a block tag on a new line.
+fn wiki_rest(rest: &str) -> String {
+    if rest.is_empty() {
+        String::new()
+    } else {
+        format!("\n{}", wiki_inline(rest))
+    }
+}
+
+/// Find the language in the parameters of a code block, such as `java` or `language=java`.
+fn code_language(parameters: &str) -> Option<&str> {
+    parameter(parameters, "language").or_else(|| {
+        parameters
+            .split('|')
+            .map(str::trim)
+            .find(|param| !param.is_empty() && !param.contains('='))
+    })
+}
+
+/// Find a named parameter of a block macro, such as `title=Example`.
+fn parameter<'a>(parameters: &'a str, name: &str) -> Option<&'a str> {
+    parameters.split('|').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+/// Convert a table row, such as `||Header||Header||` or `|Cell|Cell|`, to AsciiDoc cells.
+fn wiki_table_row(line: &str) -> String {
+    line.trim_matches('|')
+        .split('|')
+        .map(str::trim)
+        .filter(|cell| !cell.is_empty())
+        .map(|cell| format!("|{cell}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Wrap the collected table rows in an AsciiDoc table.
+fn finish_table(rows: &mut Vec<String>, header: bool) -> String {
+    let options = if header { "[options=\"header\"]\n" } else { "" };
+    let table = format!("{options}|===\n{}\n|===", rows.join("\n"));
+    rows.clear();
+    table
+}
+
+/// Convert the inline wiki markup on a single line.
+fn wiki_inline(line: &str) -> String {
+    // Keep AsciiDoc monospace spans as they are, and convert only the text around them.
+    let mut result = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find('`') {
+        let Some(length) = rest[start + 1..].find('`') else {
+            break;
+        };
+        let end = start + 1 + length;
+        result.push_str(&wiki_inline_text(&rest[..start]));
+        result.push_str(&rest[start..=end]);
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(&wiki_inline_text(rest));
+    result
+}
+
+/// Convert the inline wiki markup in text that contains no monospace spans.
+fn wiki_inline_text(text: &str) -> String {
+    let text = WIKI_COLOR.replace_all(text, "");
+    let text = WIKI_ANCHOR.replace_all(&text, "[[$1]]");
+    let text = WIKI_MONOSPACE.replace_all(&text, "`+$1+`");
+    let text = WIKI_CITATION.replace_all(&text, "__${1}__");
+    let text = WIKI_LINK.replace_all(&text, |caps: &Captures| {
+        let target = &caps[2];
+        let label = caps.get(1).map_or("", |label| label.as_str());
+
+        if let Some(anchor) = target.strip_prefix('#') {
+            if label.is_empty() {
+                format!("<<{anchor}>>")
+            } else {
+                format!("<<{anchor},{label}>>")
+            }
+        } else if let Some(user) = target.strip_prefix('~') {
+            user.to_string()
+        } else if target.contains("://") || target.starts_with("mailto:") {
+            format!("{target}[{label}]")
+        } else {
+            // Not a link, such as an AsciiDoc attribute list. Keep it.
+            caps[0].to_string()
+        }
+    });
+
+    text.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A text node with the marks.
+    fn text(text: &str, marks: &[Value]) -> Value {
+        json!({ "type": "text", "text": text, "marks": marks })
+    }
+
+    /// A paragraph of the inline nodes.
+    fn paragraph(content: &[Value]) -> Value {
+        json!({ "type": "paragraph", "content": content })
+    }
+
+    /// A list item with a paragraph, and optionally a nested list.
+    fn item(label: &str, nested: Option<Value>) -> Value {
+        let mut content = vec![paragraph(&[text(label, &[])])];
+        content.extend(nested);
+        json!({ "type": "listItem", "content": content })
+    }
+
+    #[test]
+    fn adf_to_asciidoc() {
+        let cases = [
+            (
+                "paragraphs",
+                vec![
+                    paragraph(&[text("First.", &[])]),
+                    paragraph(&[text("Second.", &[])]),
+                ],
+                "First.\n\nSecond.",
+            ),
+            (
+                "heading as a block title",
+                vec![
+                    json!({ "type": "heading", "attrs": { "level": 3 }, "content": [text("Title", &[])] }),
+                    paragraph(&[text("Text.", &[])]),
+                ],
+                ".Title\nText.",
+            ),
+            (
+                "marks",
+                vec![paragraph(&[
+                    text("bold ", &[json!({ "type": "strong" })]),
+                    text("code", &[json!({ "type": "code" })]),
+                    text(" and ", &[]),
+                    text(
+                        "docs",
+                        &[json!({ "type": "link", "attrs": { "href": "https://example.com" } })],
+                    ),
+                ])],
+                "**bold** `+code+` and https://example.com[docs]",
+            ),
+            (
+                "nested bullet list",
+                vec![json!({ "type": "bulletList", "content": [
+                    item("One", Some(json!({ "type": "bulletList", "content": [item("Nested", None)] }))),
+                    item("Two", None),
+                ] })],
+                "* One\n** Nested\n* Two",
+            ),
+            (
+                "ordered list",
+                vec![json!({ "type": "orderedList", "content": [item("Step", None)] })],
+                ". Step",
+            ),
+            (
+                "code block",
+                vec![
+                    json!({ "type": "codeBlock", "attrs": { "language": "bash" }, "content": [text("$ run *", &[])] }),
+                ],
+                "[source,bash]\n----\n$ run *\n----",
+            ),
+            (
+                "table with a header",
+                vec![json!({ "type": "table", "content": [
+                    { "type": "tableRow", "content": [
+                        { "type": "tableHeader", "content": [paragraph(&[text("Name", &[])])] },
+                    ] },
+                    { "type": "tableRow", "content": [
+                        { "type": "tableCell", "content": [paragraph(&[text("Value", &[])])] },
+                    ] },
+                ] })],
+                "[options=\"header\"]\n|===\n|Name\n|Value\n|===",
+            ),
+            (
+                "media",
+                vec![
+                    json!({ "type": "mediaSingle", "content": [{ "type": "media" }] }),
+                    paragraph(&[text("Text.", &[])]),
+                ],
+                "Text.",
+            ),
+        ];
+
+        for (name, content, expected) in cases {
+            let document = json!({ "type": "doc", "version": 1, "content": content });
+            assert!(is_adf(&document), "{name}");
+            assert_eq!(from_adf(&document), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn wiki_to_asciidoc() {
+        let cases = [
+            ("heading", "h3. Title\nText.", ".Title\nText."),
+            (
+                "code block",
+                "{code:java}\nint x = 1;\n{code}",
+                "[source,java]\n----\nint x = 1;\n----",
+            ),
+            (
+                "noformat block",
+                "{noformat}*raw*{noformat}",
+                "....\n*raw*\n....",
+            ),
+            ("quote line", "bq. Quoted.", "____\nQuoted.\n____"),
+            ("numbered list", "# One\n## Nested", ". One\n.. Nested"),
+            (
+                "table",
+                "||Name||Value||\n|a|b|",
+                "[options=\"header\"]\n|===\n|Name |Value\n|a |b\n|===",
+            ),
+            (
+                "link",
+                "See [the docs|https://example.com].",
+                "See https://example.com[the docs].",
+            ),
+            (
+                "monospace",
+                "Run {{acorns build}}.",
+                "Run `+acorns build+`.",
+            ),
+            ("citation", "??A book??", "__A book__"),
+            ("color", "{color:red}Red{color} text", "Red text"),
+            ("anchor", "{anchor:intro}Text", "[[intro]]Text"),
+        ];
+
+        for (name, wiki, expected) in cases {
+            assert_eq!(from_wiki(wiki), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn asciidoc_stays_unchanged() {
+        let cases = [
+            (
+                "title and inline markup",
+                ".Title\n\nPreviously, *bold* and `code` failed. See link:https://example.com[the docs^].",
+            ),
+            ("lists", "* Item\n** Nested\n. Step\n.. Sub-step"),
+            (
+                "listing with wiki-like content",
+                "[source,yaml]\n----\n# A comment\nh3. not a heading\n{code}\n----",
+            ),
+            ("literal block", "....\n|a|b|\n...."),
+            ("table", "[cols=\"1,2\"]\n|===\n|Name |Value\n|a |b\n|==="),
+            ("attributes and IDs", "[id=\"note\"]\n[role=\"_abstract\"]\nText."),
+            ("cross-references", "xref:other-note[the other note] and <<anchor>>."),
+            ("admonition", "NOTE: Restart the service."),
+            (
+                "wiki-like markup in monospace",
+                "Set `{{ .Values.image }}` in the chart.",
+            ),
+            ("question marks", "Use ??? or `a??b??c` now."),
+            (
+                "several monospace spans",
+                "Both `+{color}+` and `[a|https://x]` stay, with `{{x}}`.",
+            ),
+        ];
+
+        for (name, asciidoc) in cases {
+            assert_eq!(from_wiki(asciidoc), asciidoc, "{name}");
+        }
+    }
+}
@@ -29,18 +29,28 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 #![forbid(unsafe_code)]
 
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use color_eyre::eyre::{Result, WrapErr};
+use color_eyre::eyre::{bail, Result, WrapErr};
 
+mod check;
 pub mod cli;
 mod config;
 mod convert;
+mod custom_templates;
+mod diff;
 mod extra_fields;
+mod forges;
 mod init;
+mod jira_markup;
+mod local_tickets;
 mod logging;
+mod markdown;
 mod note;
 mod references;
+mod single_file;
+mod snapshot;
 mod status_report;
 mod summary_list;
 mod templating;
@@ -49,25 +59,69 @@ mod tracker_access;
 
 use cli::{Cli, Commands};
 
-// use config::tracker::Service;
-use templating::{DocumentVariant, Module};
+use config::tracker::Service;
+use config::{OutputFormat, Variant};
+use custom_templates::ModuleTemplates;
+use templating::Module;
 
-use crate::config::Project;
+use crate::config::{CustomPaths, Project};
 pub use crate::ticket_abstraction::AbstractTicket;
 
 /// Run the subcommand that the user picked on the command line.
+///
+/// # Errors
+///
+/// Returns an error if the subcommand fails at any stage, such as reading
+/// the configuration, downloading tickets, or writing the output.
 pub fn run(cli: &Cli) -> Result<()> {
     // Initialize the logging system based on the set verbosity
     logging::initialize_logger(cli.verbose)?;
 
     match &cli.command {
         // If the user picked the `build` subcommand, build the specified release notes project directory
-        Commands::Build { project } => {
-            build_rn_project(project)?;
+        Commands::Build {
+            project,
+            offline,
+            tickets,
+            trackers,
+            templates,
+            output,
+        } => {
+            let custom_paths = CustomPaths {
+                tickets: tickets.clone(),
+                trackers: trackers.clone(),
+                templates: templates.clone(),
+                output: output.clone(),
+            };
+            build_rn_project(project, *offline, &custom_paths)?;
+        }
+        // If the user picked the `check` subcommand, validate the project configuration
+        Commands::Check {
+            project,
+            tickets,
+            trackers,
+            templates,
+        } => {
+            let custom_paths = CustomPaths {
+                tickets: tickets.clone(),
+                trackers: trackers.clone(),
+                templates: templates.clone(),
+                output: None,
+            };
+            check::check(project, &custom_paths)?;
         }
         // If the user picked the `ticket` subcommand, fetch and display a single ticket
-        Commands::Ticket { .. } => {
-            display_single_ticket()?;
+        Commands::Ticket {
+            config,
+            api_key,
+            tracker,
+            id,
+        } => {
+            display_single_ticket(config, api_key.as_deref(), tracker, id)?;
+        }
+        // If the user picked the `diff` subcommand, compare the tickets in two builds
+        Commands::Diff { config, old, new } => {
+            diff::diff(old, new, config)?;
         }
         // If the user picked the `convert` subcommand, convert from the CoRN 3 config file
         Commands::Convert {
@@ -85,99 +139,234 @@ pub fn run(cli: &Cli) -> Result<()> {
 
 /// Run the `ticket` subcommand, which downloads information about the single specified ticket
 /// and prints out the release note resulting from the ticket.
-fn display_single_ticket() -> Result<()> {
-    // TODO: Tie in the ticket subcommand with the new tracker configuration.
-    todo!();
-    /*
-    log::info!("Downloading ticket information.");
-    let service = match ticket_args.value_of("service").unwrap() {
-        "jira" => Service::Jira,
-        "bugzilla" => Service::Bugzilla,
-        _ => unreachable!(),
+fn display_single_ticket(
+    trackers_file: &Path,
+    api_key: Option<&str>,
+    service: &str,
+    key: &str,
+) -> Result<()> {
+    let service = Service::from_str(service)?;
+    let trackers = config::parse_trackers(trackers_file)?;
+
+    // Display the release note as the project configures it, if the templates configuration
+    // is next to the trackers configuration. Otherwise, use the default variants in AsciiDoc.
+    let config_dir = trackers_file.parent().unwrap_or_else(|| Path::new("."));
+    let templates_file = config_dir.join("templates.yaml");
+    let (variants, format) = if templates_file.is_file() {
+        let templates = config::parse_templates(&templates_file)?;
+        (templates.variants, templates.format)
+    } else {
+        (Variant::defaults(), OutputFormat::AsciiDoc)
     };
+    // Format the release note with the custom layout of the project, if it has one.
+    let module_templates = ModuleTemplates::load(&config_dir.join("templates"), format)?;
 
-    let _ticket = tracker_access::ticket(
-        ticket_args.value_of("id").unwrap(),
-        ticket_args.value_of("api_key").unwrap(),
-        service,
-        todo!(),
-    )?;
+    log::info!("Downloading ticket information.");
+    let ticket = tracker_access::ticket(key, api_key, service, &trackers)?;
+
+    // Display the release note in every variant, because they differ in the debug information.
+    for variant in &variants {
+        println!("{}\n", comment(&format!("{variant} variant:"), format));
+
+        let owner = format!("the filter of the `{variant}` variant");
+        if !variant.filter.matches(&ticket, &owner)? {
+            println!(
+                "{}\n",
+                comment("The variant doesn't include this ticket.", format)
+            );
+        }
 
-    let variant = DocumentVariant::Internal;
-    println!("{}", ticket.release_note(&variant));
+        let release_note =
+            ticket.release_note(variant, format, module_templates.release_note.as_ref())?;
+        println!("{release_note}\n");
+    }
+
+    println!("{}\n", comment("Checks:", format));
+    print!("{}", status_report::ticket_checks(&ticket));
 
     Ok(())
-    */
+}
+
+/// A comment line in the markup language of the document.
+fn comment(text: &str, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::AsciiDoc => format!("// {text}"),
+        OutputFormat::Markdown => format!("<!-- {text} -->"),
+    }
 }
 
 /// Run the `build` subcommand, which build the release notes project that's configured
 /// in the project directory specified on the command line, or in the working directory.
-fn build_rn_project(project_dir: &Path) -> Result<()> {
-    // TODO: Recognize the optional paths to different config files.
-    let project = Project::new(project_dir)?;
+///
+/// In the offline mode, the build reuses the tickets from the snapshot of the previous build.
+///
+/// The custom paths replace the default locations of the configuration files and the output.
+fn build_rn_project(project_dir: &Path, offline: bool, custom_paths: &CustomPaths) -> Result<()> {
+    let project = Project::new(project_dir, custom_paths)?;
 
     log::info!("Building release notes in {}", &project.base_dir.display());
 
-    let document = Document::new(&project)?;
+    // Check the output directory before accessing the trackers, so that the build fails early.
+    check_output_dir(&project.generated_dir, &project.templates.variants)?;
+
+    let document = Document::new(&project, offline)?;
 
     document.write_variants(&project.generated_dir)?;
 
     Ok(())
 }
 
+/// Make sure that the output directory contains nothing but the output of an earlier build.
+/// Saving the output replaces the whole directory, so it must not contain any other files.
+///
+/// The output of an earlier build consists of the ticket snapshot, the status tables,
+/// and the files of the variants. Releases before the ticket snapshot generated only
+/// the `internal` and `external` variants.
+fn check_output_dir(generated_dir: &Path, variants: &[Variant]) -> Result<()> {
+    let entries = match fs::read_dir(generated_dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => {
+            return Err(error).wrap_err_with(|| {
+                format!(
+                    "Cannot read the output directory: {}",
+                    generated_dir.display()
+                )
+            })
+        }
+    };
+
+    let defaults = Variant::defaults();
+    let directories: Vec<&str> = variants
+        .iter()
+        .chain(&defaults)
+        .map(Variant::directory)
+        .collect();
+    // The variant subdirectory, or the single-file document of the variant.
+    let is_generated = |name: &str| {
+        name == snapshot::SNAPSHOT_FILE
+            || name.starts_with("status-table.")
+            || directories.iter().any(|directory| {
+                name.strip_prefix(directory)
+                    .is_some_and(|rest| ["", ".adoc", ".md"].contains(&rest))
+            })
+    };
+
+    for entry in entries {
+        let name = entry?.file_name();
+        if !is_generated(&name.to_string_lossy()) {
+            bail!(
+                "The output directory contains a file that the build doesn't generate: {}\n\
+                The build replaces the whole directory. Choose an empty or a new output directory.",
+                generated_dir.join(name).display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
 /// Holds all the data generated from the project configuration before writing them to disk.
 struct Document {
-    internal_modules: Vec<Module>,
-    external_modules: Vec<Module>,
+    format: OutputFormat,
+    /// The generated document in each configured variant.
+    variants: Vec<VariantDocument>,
     status_table: String,
     json_status: String,
-    internal_summary: String,
-    external_summary: String,
+    snapshot: String,
+}
+
+/// The generated files of a single document variant.
+struct VariantDocument {
+    /// The output subdirectory of the variant.
+    directory: String,
+    modules: Vec<Module>,
+    summary: String,
+    /// The complete document in a single file, if the project configures it.
+    single_file: Option<String>,
 }
 
 impl Document {
-    /// Prepare all populated and formatted modules that result from the RN project configuration.
-    /// Returns a tuple with the document generated in two variants: (Internal, External).
-    fn new(project: &Project) -> Result<Self> {
+    /// Prepare all populated and formatted modules that result from the RN project configuration,
+    /// in each configured document variant.
+    fn new(project: &Project, offline: bool) -> Result<Self> {
+        let snapshot_file = project.generated_dir.join(snapshot::SNAPSHOT_FILE);
+
+        let raw_tickets = if offline {
+            let mut raw_tickets = snapshot::load(&snapshot_file, &project.tickets)?;
+            // Local tickets never come from the snapshot, because their files might have changed.
+            raw_tickets.append(tracker_access::load_local(
+                &project.tickets,
+                &project.trackers,
+            )?);
+            raw_tickets
+        } else {
+            tracker_access::download(&project.tickets, &project.trackers)?
+        };
+
+        // Serialize the raw tickets now, because the processing consumes them.
+        let snapshot = snapshot::serialize(&raw_tickets)?;
+
         let abstract_tickets =
-            ticket_abstraction::from_queries(&project.tickets, &project.trackers)?;
-
-        // Filter internal and external tickets here before formatting the document.
-        // That way, functions in `templating` don't have to keep checking if they're
-        // working on the right ticket subset.
-        let tickets_for_internal = variant_tickets(&abstract_tickets, DocumentVariant::Internal);
-        let tickets_for_external = variant_tickets(&abstract_tickets, DocumentVariant::External);
-
-        let internal_modules = templating::format_document(
-            &tickets_for_internal,
-            &project.templates,
-            DocumentVariant::Internal,
-        );
-        let external_modules = templating::format_document(
-            &tickets_for_external,
-            &project.templates,
-            DocumentVariant::External,
-        );
+            ticket_abstraction::from_queries(&project.tickets, raw_tickets, &project.trackers)?;
+
+        let templates = &project.templates;
+        let format = templates.format;
+
+        let all_tickets: Vec<&AbstractTicket> = abstract_tickets.iter().collect();
+        templating::check_usage(&all_tickets, templates, &project.module_templates)?;
+
+        let mut variants = Vec::new();
+
+        for variant in &templates.variants {
+            // Filter the tickets of the variant here before formatting the document.
+            // That way, functions in `templating` don't have to keep checking if they're
+            // working on the right ticket subset.
+            let tickets = variant_tickets(&abstract_tickets, variant)?;
+
+            let modules = templating::format_document(
+                &tickets,
+                templates,
+                &project.module_templates,
+                variant,
+            )?;
+
+            let summary = summary_list::appendix(
+                &tickets,
+                format,
+                project.module_templates.summary_list.as_ref(),
+            )?;
+
+            let single_file = templates.single_file.then(|| {
+                single_file::document(
+                    &modules,
+                    &summary,
+                    templates.document_title.as_deref(),
+                    format,
+                )
+            });
+
+            variants.push(VariantDocument {
+                directory: variant.directory().to_string(),
+                modules,
+                summary,
+                single_file,
+            });
+        }
 
         let (status_table, json_status) = status_report::analyze_status(&abstract_tickets)?;
 
-        let internal_summary =
-            summary_list::appendix(&tickets_for_internal, DocumentVariant::Internal)?;
-        let external_summary =
-            summary_list::appendix(&tickets_for_external, DocumentVariant::External)?;
-
         Ok(Self {
-            internal_modules,
-            external_modules,
+            format,
+            variants,
             status_table,
             json_status,
-            internal_summary,
-            external_summary,
+            snapshot,
         })
     }
 
     /// Write the formatted RN modules of a document variant as files to the output directory.
-    fn write_variant(modules: &[Module], summary: &str, generated_dir: &Path) -> Result<()> {
+    fn write_variant(modules: &[Module], generated_dir: &Path) -> Result<()> {
         // Make sure that the output directory exists.
         fs::create_dir_all(generated_dir)?;
 
@@ -189,42 +378,73 @@ impl Document {
             // If the currently processed module is an assembly,
             // recursively descend into the assembly and write its included modules.
             if let Some(included_modules) = &module.included_modules {
-                Self::write_variant(included_modules, summary, generated_dir)?;
+                Self::write_variant(included_modules, generated_dir)?;
             }
-
-            // Save the appendix.
-            let summary_file = generated_dir.join("ref_list-of-tickets-by-component.adoc");
-            log::debug!("Writing file: {}", summary_file.display());
-            fs::write(summary_file, summary)
-                .wrap_err("Failed to write generated summary appendix.")?;
         }
 
         Ok(())
     }
 
     /// Write the formatted RN modules of both document variants as files to the output directory.
+    ///
+    /// The files are first written to a new staging directory. Only if that succeeds,
+    /// the staging directory replaces the previous output as a whole.
+    /// Files whose content didn't change keep their previous timestamps.
     fn write_variants(&self, generated_dir: &Path) -> Result<()> {
         log::info!("Saving the generated release notes.");
 
-        // Remove all previously generated content so that it doesn't interfere with the new build.
-        if generated_dir.exists() {
-            fs::remove_dir_all(generated_dir)?;
+        fs::create_dir_all(generated_dir)?;
+        let generated_dir = &generated_dir.canonicalize()?;
+
+        let staging_dir = create_sibling_dir(generated_dir, "tmp")?;
+        let result = self
+            .write_files(&staging_dir)
+            .and_then(|()| keep_unchanged_files(generated_dir, &staging_dir))
+            .and_then(|()| replace_dir(&staging_dir, generated_dir));
+
+        if result.is_err() && staging_dir.exists() {
+            // Report the original error rather than a failure to clean up.
+            if let Err(error) = fs::remove_dir_all(&staging_dir) {
+                log::warn!(
+                    "Failed to remove the temporary output directory {}: {error}",
+                    staging_dir.display()
+                );
+            }
         }
 
-        let internal_dir = generated_dir.join("internal");
-        let external_dir = generated_dir.join("external");
-
-        // Save the newly generated files.
-        Self::write_variant(
-            &self.internal_modules,
-            &self.internal_summary,
-            &internal_dir,
-        )?;
-        Self::write_variant(
-            &self.external_modules,
-            &self.external_summary,
-            &external_dir,
-        )?;
+        result.wrap_err("Failed to save the generated release notes.")
+    }
+
+    /// Write all the generated files to the directory.
+    fn write_files(&self, generated_dir: &Path) -> Result<()> {
+        let summary_name = format!(
+            "ref_list-of-tickets-by-component.{}",
+            self.format.extension()
+        );
+
+        for variant in &self.variants {
+            // Save the newly generated files.
+            let variant_dir = generated_dir.join(&variant.directory);
+            Self::write_variant(&variant.modules, &variant_dir)?;
+
+            // Save the appendix.
+            let summary_file = variant_dir.join(&summary_name);
+            log::debug!("Writing file: {}", summary_file.display());
+            fs::write(summary_file, &variant.summary)
+                .wrap_err("Failed to write generated summary appendix.")?;
+
+            // Save the complete document.
+            if let Some(document) = &variant.single_file {
+                let document_file = generated_dir.join(format!(
+                    "{}.{}",
+                    variant.directory,
+                    self.format.extension()
+                ));
+                log::debug!("Writing file: {}", document_file.display());
+                fs::write(document_file, document)
+                    .wrap_err("Failed to write the single-file document.")?;
+            }
+        }
 
         // Save the status table.
         let html_status_file = generated_dir.join("status-table.html");
@@ -238,22 +458,170 @@ impl Document {
         fs::write(json_status_file, &self.json_status)
             .wrap_err("Failed to write the JSON status.")?;
 
+        // Save the ticket snapshot for later offline builds.
+        let snapshot_file = generated_dir.join(snapshot::SNAPSHOT_FILE);
+        log::debug!("Writing file: {}", snapshot_file.display());
+        fs::write(snapshot_file, &self.snapshot)
+            .wrap_err("Failed to write the ticket snapshot.")?;
+
         Ok(())
     }
 }
 
-/// Select only those tickets that belong in the Internal or External variant.
-fn variant_tickets(
-    all_tickets: &[AbstractTicket],
-    variant: DocumentVariant,
-) -> Vec<&AbstractTicket> {
-    match variant {
-        // The internal variant accepts all tickets.
-        DocumentVariant::Internal => all_tickets.iter().collect(),
-        // The external variant accepts only finished and approved tickets.
-        DocumentVariant::External => all_tickets
-            .iter()
-            .filter(|t| t.doc_text_status == extra_fields::DocTextStatus::Approved)
-            .collect(),
+/// Create a new, empty directory next to the directory, on the same file system,
+/// so that renaming between them is cheap and atomic.
+///
+/// The name is unique, such as `.generated.tmp-1234-0`, and the directory didn't exist before,
+/// so removing it later can't remove anything that this build didn't create.
+fn create_sibling_dir(dir: &Path, purpose: &str) -> Result<PathBuf> {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    let process = std::process::id();
+
+    for attempt in 0..100 {
+        let sibling = dir.with_file_name(format!(".{name}.{purpose}-{process}-{attempt}"));
+        match fs::create_dir(&sibling) {
+            Ok(()) => return Ok(sibling),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                // Another build, or a leftover, uses this name. Try the next one.
+            }
+            Err(error) => {
+                return Err(error).wrap_err_with(|| {
+                    format!("Failed to create the directory {}.", sibling.display())
+                })
+            }
+        }
+    }
+
+    bail!(
+        "Failed to create a temporary directory next to {}.",
+        dir.display()
+    )
+}
+
+/// Replace the files in the staging directory that have the same content in the previous output
+/// with links to the previous files, so that the unchanged files keep their timestamps.
+fn keep_unchanged_files(previous: &Path, staging: &Path) -> Result<()> {
+    for entry in fs::read_dir(staging)? {
+        let staged_path = entry?.path();
+        let previous_path = previous.join(staged_path.strip_prefix(staging)?);
+
+        if staged_path.is_dir() {
+            if previous_path.is_dir() {
+                keep_unchanged_files(&previous_path, &staged_path)?;
+            }
+        } else if previous_path.is_file() {
+            let content = fs::read(&staged_path)?;
+            if fs::read(&previous_path)? == content {
+                fs::remove_file(&staged_path)?;
+                // If the file system can't link the files, keep the new copy.
+                if fs::hard_link(&previous_path, &staged_path).is_err() {
+                    fs::write(&staged_path, &content)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Move the new directory in place of the target directory, and remove the previous target.
+///
+/// The previous target first moves into a directory that this function creates,
+/// so that a failure never leaves the target without any output.
+fn replace_dir(new: &Path, target: &Path) -> Result<()> {
+    let previous_dir = create_sibling_dir(target, "old")?;
+    let previous = previous_dir.join("previous");
+
+    fs::rename(target, &previous).wrap_err_with(|| {
+        format!(
+            "Failed to move the previous output away from {}.",
+            target.display()
+        )
+    })?;
+
+    if let Err(error) = fs::rename(new, target) {
+        // Put the previous output back.
+        fs::rename(&previous, target)?;
+        fs::remove_dir(&previous_dir)?;
+        return Err(error)
+            .wrap_err_with(|| format!("Failed to move the new output to {}.", target.display()));
+    }
+
+    fs::remove_dir_all(&previous_dir).wrap_err_with(|| {
+        format!(
+            "Failed to remove the previous output in {}.",
+            previous_dir.display()
+        )
+    })
+}
+
+/// Select only those tickets that belong in the document variant.
+fn variant_tickets<'a>(
+    all_tickets: &'a [AbstractTicket],
+    variant: &Variant,
+) -> Result<Vec<&'a AbstractTicket>> {
+    let owner = format!("the filter of the `{variant}` variant");
+    let mut tickets = Vec::new();
+
+    for ticket in all_tickets {
+        if variant.filter.matches(ticket, &owner)? {
+            tickets.push(ticket);
+        }
+    }
+
+    Ok(tickets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_dir_with_earlier_build() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let generated = dir.path().join("generated");
+
+        // A missing or empty output directory.
+        check_output_dir(&generated, &[])?;
+        fs::create_dir(&generated)?;
+        check_output_dir(&generated, &[])?;
+
+        // The output of a release before the ticket snapshot.
+        for directory in ["internal", "external"] {
+            fs::create_dir(generated.join(directory))?;
+        }
+        fs::write(generated.join("status-table.html"), "")?;
+        fs::write(generated.join("status-table.json"), "")?;
+        check_output_dir(&generated, &[])?;
+
+        // The output of a current build with a custom variant and single-file documents.
+        let variants: Vec<Variant> = serde_yaml::from_str("[{name: preview, directory: beta}]")?;
+        fs::create_dir(generated.join("beta"))?;
+        fs::write(generated.join("beta.adoc"), "")?;
+        fs::write(generated.join("internal.md"), "")?;
+        fs::write(generated.join(snapshot::SNAPSHOT_FILE), "")?;
+        check_output_dir(&generated, &variants)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_dir_with_other_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir(dir.path().join("internal"))?;
+        fs::write(dir.path().join(snapshot::SNAPSHOT_FILE), "")?;
+        fs::write(dir.path().join("notes.txt"), "")?;
+
+        let error = check_output_dir(dir.path(), &Variant::defaults())
+            .err()
+            .ok_or_else(|| color_eyre::eyre::eyre!("The directory passed the check."))?;
+        assert!(error.to_string().contains("notes.txt"), "{error}");
+
+        // A directory of a variant that the project doesn't configure.
+        fs::remove_file(dir.path().join("notes.txt"))?;
+        fs::create_dir(dir.path().join("beta"))?;
+        assert!(check_output_dir(dir.path(), &Variant::defaults()).is_err());
+
+        Ok(())
     }
 }
@@ -0,0 +1,220 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+Tickets that the release notes project defines locally, in a directory of YAML or JSON files.
+
+Local tickets cover release notes that have no ticket in any tracker, such as one-off announcements,
+and they enable you to test templates without accessing any tracker.
+
+The name of each file, without the extension, is the key of the ticket.
+A search is a pattern of file names, where `*` matches any text.
+*/
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::extra_fields::CustomValue;
+
+/// The file extensions that can contain a ticket.
+const EXTENSIONS: &[&str] = &["yaml", "yml", "json"];
+
+/// The fields of a ticket, as written in a ticket file.
+/// They correspond to the fields of `AbstractTicket`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TicketFile {
+    pub summary: String,
+    pub description: Option<String>,
+    pub doc_type: String,
+    #[serde(default)]
+    pub doc_text: String,
+    /// The doc text status uses the same values as in the trackers, such as `Done` or `In progress`.
+    #[serde(default = "default_doc_text_status")]
+    pub doc_text_status: String,
+    pub docs_contact: Option<String>,
+    #[serde(default = "default_status")]
+    pub status: String,
+    pub resolution: Option<String>,
+    #[serde(default = "default_is_open")]
+    pub is_open: bool,
+    pub priority: Option<String>,
+    /// If the ticket has a URL, the release note links to it.
+    pub url: Option<String>,
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub components: Vec<String>,
+    #[serde(default)]
+    pub product: String,
+    pub labels: Option<Vec<String>>,
+    pub flags: Option<Vec<String>>,
+    #[serde(default)]
+    pub target_releases: Vec<String>,
+    #[serde(default)]
+    pub subsystems: Vec<String>,
+    pub groups: Option<Vec<String>>,
+    /// By default, a ticket is public if it has a URL.
+    pub public: Option<bool>,
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, CustomValue>,
+}
+
+fn default_doc_text_status() -> String {
+    "Done".to_string()
+}
+
+fn default_status() -> String {
+    "New".to_string()
+}
+
+fn default_is_open() -> bool {
+    true
+}
+
+/// A ticket loaded from a ticket file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalTicket {
+    /// The name of the ticket file without the extension.
+    pub key: String,
+    pub fields: TicketFile,
+}
+
+/// Access to the directory that contains the ticket files.
+pub struct LocalDirectory {
+    directory: PathBuf,
+}
+
+impl LocalDirectory {
+    pub fn at(directory: &Path) -> Result<Self> {
+        if !directory.is_dir() {
+            bail!(
+                "The local tickets directory doesn't exist: {}",
+                directory.display()
+            );
+        }
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+        })
+    }
+
+    /// List all ticket files in the directory, with their keys, sorted by the key.
+    /// Each key can only have a single file.
+    fn files(&self) -> Result<Vec<(String, PathBuf)>> {
+        let entries = fs::read_dir(&self.directory).wrap_err_with(|| {
+            format!(
+                "Cannot read the local tickets directory: {}",
+                self.directory.display()
+            )
+        })?;
+
+        let mut files = Vec::new();
+
+        for entry in entries {
+            let path = entry?.path();
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            let key = path.file_stem().and_then(|stem| stem.to_str());
+
+            if let (Some(extension), Some(key)) = (extension, key) {
+                if EXTENSIONS.contains(&extension) && path.is_file() {
+                    files.push((key.to_string(), path.clone()));
+                }
+            }
+        }
+
+        files.sort();
+
+        // Two files with the same name and different extensions would define the same ticket twice.
+        if let Some(pair) = files.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            bail!(
+                "Two local ticket files define the {} ticket: {} and {}",
+                pair[0].0,
+                pair[0].1.display(),
+                pair[1].1.display()
+            );
+        }
+
+        Ok(files)
+    }
+
+    /// Load the tickets that have these keys.
+    /// Like Bugzilla, silently skip the keys that have no ticket file.
+    pub fn tickets(&self, keys: &[&str]) -> Result<Vec<LocalTicket>> {
+        self.files()?
+            .into_iter()
+            .filter(|(key, _path)| keys.contains(&key.as_str()))
+            .map(|(key, path)| read_ticket(key, &path))
+            .collect()
+    }
+
+    /// Load all tickets whose keys match this pattern.
+    pub fn search(&self, pattern: &str) -> Result<Vec<LocalTicket>> {
+        self.files()?
+            .into_iter()
+            .filter(|(key, _path)| wildcard_match(pattern, key))
+            .map(|(key, path)| read_ticket(key, &path))
+            .collect()
+    }
+}
+
+/// Parse a single ticket file, either as JSON or as YAML.
+fn read_ticket(key: String, path: &Path) -> Result<LocalTicket> {
+    let text = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Cannot read the ticket file: {}", path.display()))?;
+
+    let fields: TicketFile = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&text)
+            .wrap_err_with(|| format!("Cannot parse the ticket file: {}", path.display()))?
+    } else {
+        serde_yaml::from_str(&text)
+            .wrap_err_with(|| format!("Cannot parse the ticket file: {}", path.display()))?
+    };
+
+    Ok(LocalTicket { key, fields })
+}
+
+/// Check if the text matches the pattern, where `*` matches any text, including none.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // The pattern always has at least one part, even if it's empty.
+    let first = parts.next().unwrap_or_default();
+
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let middle: Vec<&str> = parts.collect();
+
+    // Without any `*`, the pattern must match the complete text.
+    let Some((last, middle)) = middle.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
@@ -0,0 +1,310 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+A conversion of the common AsciiDoc constructs in doc text to Markdown.
+
+Release notes in tickets are written in AsciiDoc. When the document is generated
+in Markdown, this module converts the constructs that release notes typically use:
+
+* Block titles, such as `.Title`, and section headings
+* Links, cross-references, and bare URLs with a link text
+* Bold text, and monospace text with the `+` passthrough
+* Nested bulleted and numbered lists, and list continuations
+* Source and literal blocks
+* Admonitions, such as `NOTE:`
+* Comments, which are removed
+
+Anything else passes through unchanged.
+*/
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+/// A block title, such as `.Title`, but not a numbered list item such as `. Item`.
+static BLOCK_TITLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\.([^.\s].*)$").expect("Invalid block title regex."));
+
+/// A section heading, such as `== Title`.
+static HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(=+)\s+(.+)$").expect("Invalid heading regex."));
+
+/// A bulleted or numbered list item, with its nesting depth in the markers.
+static LIST_ITEM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\*+|\.+|-)\s+(.*)$").expect("Invalid list item regex."));
+
+/// An admonition paragraph, such as `NOTE: Text`.
+static ADMONITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(NOTE|TIP|IMPORTANT|WARNING|CAUTION):\s+(.*)$")
+        .expect("Invalid admonition regex.")
+});
+
+/// An ID attribute line, such as `[id="anchor"]` or `[[anchor]]`.
+static ID_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^(?:\[id="?([^"\],]+)"?.*\]|\[\[([^\],]+)\]\])$"#).expect("Invalid ID regex.")
+});
+
+/// The attribute line of a source block, such as `[source,bash]`.
+static SOURCE_LINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[source(?:,\s*([^,\]]+))?.*\]$").expect("Invalid source regex."));
+
+/// Any other block attribute line, such as `[role="_abstract"]`.
+static ATTRIBUTE_LINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[[^\[\]]*\]$").expect("Invalid attribute regex."));
+
+/// A link macro, such as `link:https://example.com[Text]`, or a URL with a link text.
+static LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:link:)?((?:https?|ftp|mailto):[^\s\[\]]+|link:[^\s\[\]]+)\[([^\]]*)\]")
+        .expect("Invalid link regex.")
+});
+
+/// A cross-reference macro, such as `xref:anchor[Text]`.
+static XREF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"xref:([^\s\[\]]+)\[([^\]]*)\]").expect("Invalid xref regex."));
+
+/// A cross-reference in the short form, such as `<<anchor,Text>>`.
+static SHORT_XREF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<<([^,>\s]+)(?:,\s*([^>]+))?>>").expect("Invalid short xref regex."));
+
+/// Monospace text with a passthrough, such as `+text+`.
+static PASSTHROUGH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(^|\W)\+([^+\s](?:[^+]*[^+\s])?)\+($|\W)").expect("Invalid passthrough regex.")
+});
+
+/// Constrained bold text, such as `*text*`.
+static BOLD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(^|[^\w*])\*([^\s*](?:[^*]*[^\s*])?)\*($|[^\w*])").expect("Invalid bold regex.")
+});
+
+/// Convert the AsciiDoc text to Markdown.
+pub fn from_asciidoc(text: &str) -> String {
+    let mut output: Vec<String> = Vec::new();
+    // The delimiter of the code block that's currently open, if any.
+    let mut code_block: Option<&str> = None;
+    // The language from the `[source]` line that precedes the next code block.
+    let mut language = String::new();
+    let mut in_comment = false;
+    // The indentation of the list item that a `+` continuation attaches to.
+    let mut list_indent = String::new();
+    // A block title ends with a blank line, so the blank line that follows it in AsciiDoc is redundant.
+    let mut after_title = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_end();
+        let follows_title = std::mem::take(&mut after_title);
+
+        if let Some(delimiter) = code_block {
+            if trimmed == delimiter {
+                code_block = None;
+                output.push(format!("{list_indent}```"));
+            } else {
+                output.push(format!("{list_indent}{line}"));
+            }
+            continue;
+        }
+
+        if trimmed == "////" {
+            in_comment = !in_comment;
+            continue;
+        }
+        if in_comment || trimmed.starts_with("//") {
+            continue;
+        }
+
+        if trimmed == "----" || trimmed == "...." {
+            code_block = Some(if trimmed == "----" { "----" } else { "...." });
+            output.push(format!("{list_indent}```{language}"));
+            language.clear();
+            continue;
+        }
+
+        if let Some(caps) = SOURCE_LINE.captures(trimmed) {
+            language = caps
+                .get(1)
+                .map_or_else(String::new, |lang| lang.as_str().trim().to_string());
+            continue;
+        }
+
+        if let Some(caps) = ID_LINE.captures(trimmed) {
+            let id = caps
+                .get(1)
+                .or_else(|| caps.get(2))
+                .map_or("", |id| id.as_str());
+            output.push(format!("<a id=\"{id}\"></a>"));
+            continue;
+        }
+
+        if ATTRIBUTE_LINE.is_match(trimmed) {
+            continue;
+        }
+
+        // A list continuation attaches the following block to the list item.
+        if trimmed == "+" {
+            output.push(String::new());
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            list_indent.clear();
+            if !follows_title {
+                output.push(String::new());
+            }
+            continue;
+        }
+
+        let converted = if let Some(caps) = BLOCK_TITLE.captures(trimmed) {
+            list_indent.clear();
+            after_title = true;
+            // Markdown has no block titles. Use a bold paragraph instead, which needs
+            // a blank line to stay separate from the block.
+            // The whole title is bold, so drop any bold text inside it.
+            format!("**{}**\n", inline(&caps[1]).replace("**", ""))
+        } else if let Some(caps) = HEADING.captures(trimmed) {
+            list_indent.clear();
+            format!("{} {}", "#".repeat(caps[1].len()), inline(&caps[2]))
+        } else if let Some(caps) = LIST_ITEM.captures(trimmed) {
+            let marker = &caps[1];
+            let depth = if marker == "-" { 1 } else { marker.len() };
+            let indent = "   ".repeat(depth - 1);
+            let bullet = if marker.starts_with('.') { "1." } else { "*" };
+            list_indent = format!("{indent}   ");
+            format!("{indent}{bullet} {}", inline(&caps[2]))
+        } else if let Some(caps) = ADMONITION.captures(trimmed) {
+            let label = &caps[1];
+            let label = format!("{}{}", &label[..1], label[1..].to_lowercase());
+            format!("> **{label}:** {}", inline(&caps[2]))
+        } else {
+            format!("{list_indent}{}", inline(trimmed))
+        };
+
+        output.push(converted);
+    }
+
+    output.join("\n")
+}
+
+/// Convert the inline AsciiDoc markup on a single line.
+fn inline(line: &str) -> String {
+    // Keep the content of monospace spans as it is, and convert only the text around them.
+    let mut result = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find('`') {
+        let Some(length) = rest[start + 1..].find('`') else {
+            break;
+        };
+        let end = start + 1 + length;
+        result.push_str(&inline_text(&rest[..start]));
+        let code = &rest[start + 1..end];
+        let code = code
+            .strip_prefix('+')
+            .and_then(|code| code.strip_suffix('+'))
+            .unwrap_or(code);
+        result.push('`');
+        result.push_str(code);
+        result.push('`');
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(&inline_text(rest));
+    result
+}
+
+/// Convert the inline AsciiDoc markup in text that contains no monospace spans.
+fn inline_text(text: &str) -> String {
+    let text = XREF.replace_all(text, |caps: &Captures| {
+        let target = caps[1].trim_end_matches(".adoc");
+        let label = if caps[2].is_empty() { target } else { &caps[2] };
+        format!("[{label}](#{target})")
+    });
+    let text = SHORT_XREF.replace_all(&text, |caps: &Captures| {
+        let target = &caps[1];
+        let label = caps.get(2).map_or(target, |label| label.as_str());
+        format!("[{label}](#{target})")
+    });
+    let text = LINK.replace_all(&text, |caps: &Captures| {
+        let url = caps[1].trim_start_matches("link:");
+        if caps[2].is_empty() {
+            format!("<{url}>")
+        } else {
+            // Drop the link attributes, such as `^` for a new window.
+            let label = caps[2].trim_end_matches('^');
+            format!("[{label}]({url})")
+        }
+    });
+    let mut text = text.into_owned();
+
+    // The patterns share the characters around the markup, so adjacent spans
+    // need another pass.
+    for (pattern, replacement) in [(&*PASSTHROUGH, "$1`$2`$3"), (&*BOLD, "$1**$2**$3")] {
+        loop {
+            let replaced = pattern.replace_all(&text, replacement).into_owned();
+            if replaced == text {
+                break;
+            }
+            text = replaced;
+        }
+    }
+
+    text
+}
+
+/// Move all headings in the Markdown text one level deeper, such as from `#` to `##`.
+pub fn shift_headings(text: &str) -> String {
+    let mut in_code_block = false;
+
+    text.lines()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+
+            let is_heading = !in_code_block
+                && line.starts_with('#')
+                && line.trim_start_matches('#').starts_with(' ');
+
+            if is_heading {
+                format!("#{line}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_titles() {
+        assert_eq!(
+            from_asciidoc(".Title\nA paragraph."),
+            "**Title**\n\nA paragraph."
+        );
+        assert_eq!(
+            from_asciidoc(".Title\n\nA paragraph."),
+            "**Title**\n\nA paragraph."
+        );
+        assert_eq!(
+            from_asciidoc(".Example\n----\ncode\n----\n\nText"),
+            "**Example**\n\n```\ncode\n```\n\nText"
+        );
+    }
+}
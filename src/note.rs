@@ -16,49 +16,151 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::templating::DocumentVariant;
+use color_eyre::eyre::{Result, WrapErr};
+use serde_json::json;
+
+use crate::config::{OutputFormat, Variant};
+use crate::custom_templates::CustomTemplate;
+use crate::markdown;
 use crate::ticket_abstraction::AbstractTicket;
 
 impl AbstractTicket {
     /// Compose a release note from an abstract ticket.
-    #[must_use]
-    pub fn release_note(&self, variant: DocumentVariant) -> String {
-        let anchor = self.anchor_declaration();
+    ///
+    /// If the project provides a custom release note layout, render the note with it.
+    /// Otherwise, use the built-in layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the custom layout fails to render.
+    pub fn release_note(
+        &self,
+        variant: &Variant,
+        format: OutputFormat,
+        layout: Option<&CustomTemplate>,
+    ) -> Result<String> {
+        match layout {
+            Some(layout) => {
+                let variables = json!({
+                    "ticket": self,
+                    "anchor": self.anchor(),
+                    "doc_text": self.formatted_doc_text(format),
+                    "empty": content_lines(&self.doc_text).is_empty(),
+                    "variant": variant.name,
+                    "debug": variant.debug,
+                    "signatures": self.signature_list(format),
+                });
+                layout
+                    .render(&variables)
+                    .wrap_err_with(|| format!("Failed to format the release note of {}.", self.id))
+            }
+            None => Ok(self.default_release_note(variant, format)),
+        }
+    }
 
+    /// Compose a release note using the built-in layout.
+    fn default_release_note(&self, variant: &Variant, format: OutputFormat) -> String {
         // This debug information line appears at empty release notes
-        // and everywhere in the Internal document variant.
-        let debug_info = format!(
-            "| {} | {} | link:{}[]",
-            &self.docs_contact, self.doc_text_status, &self.url
-        );
+        // and everywhere in the variants that show debug information.
+        let (anchor, link) = match format {
+            OutputFormat::AsciiDoc => (self.anchor_declaration(), format!("link:{}[]", &self.url)),
+            OutputFormat::Markdown => (
+                format!("<a id=\"{}\"></a>", self.anchor()),
+                format!("<{}>", &self.url),
+            ),
+        };
+        let mut debug_info = format!("| {} | {}", &self.docs_contact, self.doc_text_status);
+        // Local tickets might have no URL to link to.
+        if !self.url.is_empty() {
+            debug_info = format!("{debug_info} | {link}");
+        }
 
-        // A placeholder for release notes with an empty doc text.
-        let empty = format!(
-            "{}\n.🚧 {} {} \n\n**No release note.**",
-            anchor, self.summary, debug_info,
-        );
+        // In the debugging variants, the ticket description helps to write the release note.
+        let description = if variant.debug {
+            self.description_block(format)
+        } else {
+            String::new()
+        };
 
         // TODO: Handle the empty doc text earlier as an error.
-        if content_lines(&self.doc_text).is_empty() {
-            empty
+        let note = if content_lines(&self.doc_text).is_empty() {
+            // A placeholder for release notes with an empty doc text.
+            match format {
+                OutputFormat::AsciiDoc => format!(
+                    "{}\n.🚧 {} {} \n\n**No release note.**",
+                    anchor, self.summary, debug_info,
+                ),
+                OutputFormat::Markdown => format!(
+                    "{}\n**🚧 {}** {}\n\n**No release note.**",
+                    anchor, self.summary, debug_info,
+                ),
+            }
         } else {
-            // If the doc text contains DOS line endings (`\r`), remove them
-            // and keep just UNIX endings (`\n`).
-            let doc_text_unix = self.doc_text.replace('\r', "");
-
             // This is the resulting release note:
             format!(
                 "{}\n{}\n\n{} {}",
                 anchor,
-                doc_text_unix,
-                self.all_signatures(),
-                // In the internal variant, add the debug information line.
-                if variant == DocumentVariant::Internal {
-                    &debug_info
-                } else {
-                    ""
-                },
+                self.formatted_doc_text(format),
+                self.signature_list(format).join(", "),
+                // In the debugging variants, add the debug information line.
+                if variant.debug { &debug_info } else { "" },
             )
+        };
+
+        note + &description
+    }
+
+    /// Show the ticket description as a collapsible block of plain text,
+    /// or nothing if the ticket has no description.
+    fn description_block(&self, format: OutputFormat) -> String {
+        let Some(description) = self
+            .description
+            .as_deref()
+            .map(|text| text.replace('\r', ""))
+            .filter(|text| !text.trim().is_empty())
+        else {
+            return String::new();
+        };
+        let description = description.trim_end();
+
+        match format {
+            OutputFormat::AsciiDoc => {
+                // The delimiter must be longer than any line of dots in the description.
+                let longest_dots = description
+                    .lines()
+                    .filter(|line| !line.is_empty() && line.chars().all(|c| c == '.'))
+                    .map(str::len)
+                    .max()
+                    .unwrap_or(0);
+                let delimiter = ".".repeat(longest_dots.max(3) + 1);
+                format!(
+                    "\n\n[%collapsible]\n.Description\n====\n{delimiter}\n{description}\n{delimiter}\n===="
+                )
+            }
+            OutputFormat::Markdown => {
+                let longest_ticks = description
+                    .lines()
+                    .filter(|line| line.starts_with("```"))
+                    .map(|line| line.chars().take_while(|c| *c == '`').count())
+                    .max()
+                    .unwrap_or(0);
+                let fence = "`".repeat(longest_ticks.max(2) + 1);
+                format!(
+                    "\n\n<details><summary>Description</summary>\n\n{fence}\n{description}\n{fence}\n\n</details>"
+                )
+            }
+        }
+    }
+
+    /// Prepare the doc text in the output format.
+    fn formatted_doc_text(&self, format: OutputFormat) -> String {
+        // If the doc text contains DOS line endings (`\r`), remove them
+        // and keep just UNIX endings (`\n`).
+        let doc_text_unix = self.doc_text.replace('\r', "");
+
+        match format {
+            OutputFormat::AsciiDoc => doc_text_unix,
+            OutputFormat::Markdown => markdown::from_asciidoc(&doc_text_unix),
         }
     }
 
@@ -70,8 +172,8 @@ impl AbstractTicket {
     pub fn signature(&self) -> String {
         let id = &self.id;
 
-        if self.public {
-            // If the ticket is public, add a clickable link.
+        if self.public && !self.url.is_empty() {
+            // If the ticket is public and has a URL, add a clickable link.
             format!("link:{}[{}]", &self.url, id)
         } else {
             // If the ticket is private, add a footnote that explains
@@ -88,17 +190,22 @@ impl AbstractTicket {
         }
     }
 
-    /// Prepare a list with signatures to this ticket and all its optional references.
-    /// The result is a comma-separated list of signatures, enclosed in parentheses.
-    #[must_use]
-    fn all_signatures(&self) -> String {
+    /// Prepare the signature of this ticket, followed by the signatures of its optional references,
+    /// in the output format.
+    fn signature_list(&self, format: OutputFormat) -> Vec<String> {
         let mut signatures = vec![self.signature()];
 
         if let Some(references) = self.references.as_ref() {
             signatures.append(&mut references.clone());
         }
 
-        signatures.join(", ")
+        match format {
+            OutputFormat::AsciiDoc => signatures,
+            OutputFormat::Markdown => signatures
+                .iter()
+                .map(|signature| markdown::from_asciidoc(signature))
+                .collect(),
+        }
     }
 
     /// Format an ID, or an anchor, that this release note can set and that you can use
@@ -107,8 +214,12 @@ impl AbstractTicket {
     /// For example, `BZ-12345`.
     #[must_use]
     pub fn anchor(&self) -> String {
-        let service = self.id.tracker.short_name();
-        let key = &self.id.key;
+        let service = self.id.tracker.short_name;
+        // Forge keys such as `owner/repo#123` contain characters that aren't valid in an ID.
+        let key = self.id.key.replace(
+            |c: char| !(c.is_alphanumeric() || c == '-' || c == '_'),
+            "-",
+        );
 
         // TODO: This anchor isn't unique across the document if the RN is reused.
         format!("{service}-{key}")
@@ -24,7 +24,7 @@ use color_eyre::Result;
 
 use crate::config::tracker;
 use crate::config::TicketQuery;
-use crate::ticket_abstraction::IntoAbstract;
+use crate::tracker_access::AnnotatedRaw;
 
 /// A newtype that captures a list of ticket queries that are references,
 /// formerly attached to actual release note ticket queries.
@@ -50,14 +50,17 @@ impl From<&[Arc<TicketQuery>]> for ReferenceQueries {
 pub struct ReferenceSignatures(HashMap<Arc<TicketQuery>, Vec<String>>);
 
 impl ReferenceSignatures {
-    pub fn new<T: IntoAbstract, U: IntoAbstract>(
-        ref_bugs: Vec<(Arc<TicketQuery>, T)>,
-        ref_issues: Vec<(Arc<TicketQuery>, U)>,
-        config: &tracker::Config,
-    ) -> Result<Self> {
+    pub fn new(ref_tickets: Vec<AnnotatedRaw>, trackers: &tracker::Config) -> Result<Self> {
         let mut signatures: HashMap<Arc<TicketQuery>, Vec<String>> = HashMap::new();
-        Self::store(&mut signatures, ref_bugs, &config.bugzilla)?;
-        Self::store(&mut signatures, ref_issues, &config.jira)?;
+
+        // Render the signatures of the tickets and record them by their query.
+        for (query, raw_ticket) in ref_tickets {
+            let ticket = raw_ticket.into_abstract(None, trackers)?;
+            signatures
+                .entry(query)
+                .and_modify(|e| e.push(ticket.signature()))
+                .or_insert_with(|| vec![ticket.signature()]);
+        }
 
         // For each ticket, sort its references alphabetically.
         // Otherwise, the order changes based on the response from the ticket tracker,
@@ -71,24 +74,6 @@ impl ReferenceSignatures {
         Ok(Self(signatures))
     }
 
-    /// A helper when building `ReferenceSignatures`. Abstracts over Bugzilla and Jira issues.
-    /// Renders the signatures of the issues and records them in the shared `HashMap`.
-    fn store<T: IntoAbstract>(
-        signatures: &mut HashMap<Arc<TicketQuery>, Vec<String>>,
-        ref_issues: Vec<(Arc<TicketQuery>, T)>,
-        config: &impl tracker::FieldsConfig,
-    ) -> Result<()> {
-        for (query, issue) in ref_issues {
-            let ticket = issue.into_abstract(None, config)?;
-            signatures
-                .entry(query)
-                .and_modify(|e| e.push(ticket.signature()))
-                .or_insert_with(|| vec![ticket.signature()]);
-        }
-
-        Ok(())
-    }
-
     /// Find references that belong to a ticket and return a list of them as signature strings.
     pub fn reattach_to(&self, main_query: &Arc<TicketQuery>) -> Vec<String> {
         let needed_references = &main_query.references;
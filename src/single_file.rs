@@ -0,0 +1,64 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+A complete, self-contained document assembled from the generated modules.
+
+The document embeds the chapters with their included modules, which the assembly templates
+render in place of the include statements, so it doesn't depend on any other file.
+*/
+
+use crate::config::OutputFormat;
+use crate::markdown;
+use crate::templating::Module;
+
+/// The title of the document if the templates configuration doesn't set one.
+const DEFAULT_TITLE: &str = "Release notes";
+
+/// Assemble the chapters and the summary appendix into a single document.
+pub fn document(
+    chapters: &[Module],
+    summary: &str,
+    title: Option<&str>,
+    format: OutputFormat,
+) -> String {
+    let title = title.unwrap_or(DEFAULT_TITLE);
+
+    let heading = match format {
+        OutputFormat::AsciiDoc => format!("= {title}"),
+        OutputFormat::Markdown => format!("# {title}"),
+    };
+
+    let mut parts = vec![heading];
+    parts.extend(
+        chapters
+            .iter()
+            .map(|chapter| embed(&chapter.inlined_text, format)),
+    );
+    parts.push(embed(summary, format));
+
+    parts.join("\n\n") + "\n"
+}
+
+/// Nest the text one level deeper in the document, the same way as an include statement does.
+pub fn embed(text: &str, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::AsciiDoc => format!(":leveloffset: +1\n\n{text}\n\n:leveloffset: -1"),
+        OutputFormat::Markdown => markdown::shift_headings(text),
+    }
+}
@@ -0,0 +1,422 @@
+/*
+acorns: Generate an AsciiDoc release notes document from tracking tickets.
+Copyright (C) 2022  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*!
+A snapshot of all the raw tickets that a build downloaded from the ticket trackers.
+
+The snapshot stores tickets in the same JSON format that the trackers provide,
+so an offline build can process them again with a changed configuration,
+without accessing the network.
+
+The snapshot only stores the tickets of remote trackers. The tickets in local files
+are part of the project, so an offline build reads their current version.
+*/
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use bugzilla_query::{Bug, Component, Flag, User, Version};
+use color_eyre::eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::config::{tracker, KeyOrSearch, TicketQuery};
+use crate::references::ReferenceQueries;
+use crate::tracker_access::{self, AnnotatedRaw, RawTickets};
+
+/// The name of the snapshot file inside the generated directory.
+pub const SNAPSHOT_FILE: &str = "tickets-snapshot.json";
+
+/// All the raw tickets that resulted from a single query.
+///
+/// The snapshot identifies the query only by its tracker and its key or search,
+/// because overrides and references don't affect the downloaded tickets.
+#[derive(Serialize, Deserialize)]
+struct SnapshotEntry {
+    tracker: tracker::Service,
+    using: KeyOrSearch,
+    /// True if the query only appears as a reference, and not as a release note ticket.
+    #[serde(default)]
+    reference: bool,
+    tickets: Vec<Value>,
+}
+
+/// Serialize the raw tickets as a JSON snapshot, ready to save to a file.
+pub fn serialize(raw: &RawTickets) -> Result<String> {
+    let mut entries: Vec<SnapshotEntry> = Vec::new();
+    // The query that created each entry. Several queries might share the same key or search,
+    // and their tickets are the same, so store the tickets only once.
+    let mut owners: Vec<Arc<TicketQuery>> = Vec::new();
+
+    // Plain tickets come before references, so that a query that appears
+    // in both roles is recorded as a plain query.
+    let tickets = raw
+        .plain
+        .iter()
+        .map(|pair| (pair, false))
+        .chain(raw.refs.iter().map(|pair| (pair, true)));
+
+    for ((query, raw_ticket), reference) in tickets {
+        if !tracker_access::is_remote(query.tracker) {
+            continue;
+        }
+
+        let ticket = raw_ticket.to_snapshot()?;
+        let position = entries
+            .iter()
+            .position(|e| e.tracker == query.tracker && e.using == query.using);
+
+        if let Some(index) = position {
+            if Arc::ptr_eq(&owners[index], query) {
+                entries[index].tickets.push(ticket);
+            }
+        } else {
+            entries.push(SnapshotEntry {
+                tracker: query.tracker,
+                using: query.using.clone(),
+                reference,
+                tickets: vec![ticket],
+            });
+            owners.push(Arc::clone(query));
+        }
+    }
+
+    serde_json::to_string_pretty(&entries).wrap_err("Failed to serialize the ticket snapshot.")
+}
+
+/// Load the raw tickets for the configured queries from a snapshot file.
+/// The result leaves out the tickets of trackers that aren't remote.
+///
+/// Returns an error if the snapshot is missing any of the queries,
+/// such as when the tickets configuration changed since the snapshot was saved.
+pub fn load(snapshot_file: &Path, queries: &[Arc<TicketQuery>]) -> Result<RawTickets> {
+    log::info!("Loading tickets from {}", snapshot_file.display());
+
+    let entries = read_entries(snapshot_file)?;
+
+    let ref_queries = ReferenceQueries::from(queries);
+
+    Ok(RawTickets {
+        plain: tickets_for(queries, &entries)?,
+        refs: tickets_for(&ref_queries.0, &entries)?,
+    })
+}
+
+/// Load all the release note tickets from a snapshot file, regardless of the project configuration.
+///
+/// Each ticket receives a query reconstructed from the snapshot, with no overrides or references.
+pub fn load_all(snapshot_file: &Path) -> Result<RawTickets> {
+    let entries = read_entries(snapshot_file)?;
+
+    let queries: Vec<Arc<TicketQuery>> = entries
+        .iter()
+        .filter(|entry| !entry.reference && tracker_access::is_remote(entry.tracker))
+        .map(|entry| {
+            Arc::new(TicketQuery {
+                tracker: entry.tracker,
+                using: entry.using.clone(),
+                overrides: None,
+                references: Vec::new(),
+            })
+        })
+        .collect();
+
+    Ok(RawTickets {
+        plain: tickets_for(&queries, &entries)?,
+        refs: Vec::new(),
+    })
+}
+
+/// Read and parse the entries in a snapshot file.
+fn read_entries(snapshot_file: &Path) -> Result<Vec<SnapshotEntry>> {
+    let text = fs::read_to_string(snapshot_file).wrap_err_with(|| {
+        format!(
+            "Cannot read the ticket snapshot: {}\nBuild the project online first.",
+            snapshot_file.display()
+        )
+    })?;
+
+    serde_json::from_str(&text).wrap_err("Cannot parse the ticket snapshot.")
+}
+
+/// Find the snapshot entries for these queries and deserialize their tickets.
+fn tickets_for(
+    queries: &[Arc<TicketQuery>],
+    entries: &[SnapshotEntry],
+) -> Result<Vec<AnnotatedRaw>> {
+    let mut tickets = Vec::new();
+
+    for query in queries {
+        // Snapshots from earlier releases might contain local tickets, which are out of date.
+        if !tracker_access::is_remote(query.tracker) {
+            continue;
+        }
+
+        let Some(entry) = entries
+            .iter()
+            .find(|e| e.tracker == query.tracker && e.using == query.using)
+        else {
+            bail!(
+                "The ticket snapshot doesn't contain this query. Build the project online first:\n{:#?}",
+                query
+            );
+        };
+
+        for ticket in &entry.tickets {
+            let raw_ticket = tracker_access::raw_from_snapshot(query.tracker, ticket.clone())?;
+            tickets.push((Arc::clone(query), raw_ticket));
+        }
+    }
+
+    Ok(tickets)
+}
+
+/// Convert a Bugzilla bug back to the JSON format of the Bugzilla REST API.
+///
+/// The `bugzilla_query` crate can only deserialize bugs, so this function lists all the fields manually.
+/// The fields are split into two objects only to stay within the recursion limit of the `json!` macro.
+pub fn bug_to_json(bug: &Bug) -> Value {
+    let mut value = json!({
+        "op_sys": bug.op_sys,
+        "classification": bug.classification,
+        "id": bug.id,
+        "url": bug.url,
+        "creator": bug.creator,
+        "creator_detail": user_to_json(&bug.creator_detail),
+        "summary": bug.summary,
+        "status": bug.status,
+        "estimated_time": bug.estimated_time,
+        "target_milestone": bug.target_milestone,
+        "cc": bug.cc,
+        "cc_detail": bug.cc_detail.iter().map(user_to_json).collect::<Vec<_>>(),
+        "is_open": bug.is_open,
+        "is_creator_accessible": bug.is_creator_accessible,
+        "docs_contact": bug.docs_contact,
+        "docs_contact_detail": bug.docs_contact_detail.as_ref().map(user_to_json),
+        "assigned_to": bug.assigned_to,
+        "assigned_to_detail": user_to_json(&bug.assigned_to_detail),
+    });
+
+    let rest = json!({
+        "resolution": bug.resolution,
+        "severity": bug.severity,
+        "product": bug.product,
+        "platform": bug.platform,
+        "last_change_time": bug.last_change_time,
+        "remaining_time": bug.remaining_time,
+        "priority": bug.priority,
+        "whiteboard": bug.whiteboard,
+        "creation_time": bug.creation_time,
+        "is_confirmed": bug.is_confirmed,
+        "qa_contact": bug.qa_contact,
+        "qa_contact_detail": bug.qa_contact_detail.as_ref().map(user_to_json),
+        "dupe_of": bug.dupe_of,
+        "target_release": bug.target_release.as_ref().map(version_to_json),
+        "actual_time": bug.actual_time,
+        "component": match &bug.component {
+            Component::One(component) => json!(component),
+            Component::Many(components) => json!(components),
+        },
+        "is_cc_accessible": bug.is_cc_accessible,
+        "version": version_to_json(&bug.version),
+        "keywords": bug.keywords,
+        "depends_on": bug.depends_on,
+        "blocks": bug.blocks,
+        "see_also": bug.see_also,
+        "groups": bug.groups,
+        "deadline": bug.deadline,
+        "update_token": bug.update_token,
+        "work_time": bug.work_time,
+        "flags": bug.flags.as_ref().map(|flags| flags.iter().map(flag_to_json).collect::<Vec<_>>()),
+        "tags": bug.tags,
+        "dependent_products": bug.dependent_products,
+    });
+
+    merge_fields(&mut value, &rest);
+    merge_fields(&mut value, &bug.extra);
+
+    // Only the first comment, the bug description, is useful. Keep the snapshot small.
+    if let Some(comments) = value.get_mut("comments").and_then(Value::as_array_mut) {
+        comments.truncate(1);
+    }
+
+    value
+}
+
+/// Convert a Bugzilla user to JSON.
+fn user_to_json(user: &User) -> Value {
+    let mut value = json!({
+        "email": user.email,
+        "id": user.id,
+        "name": user.name,
+        "real_name": user.real_name,
+    });
+
+    merge_fields(&mut value, &user.extra);
+    value
+}
+
+/// Convert a Bugzilla flag to JSON.
+fn flag_to_json(flag: &Flag) -> Value {
+    let mut value = json!({
+        "id": flag.id,
+        "type_id": flag.type_id,
+        "creation_date": flag.creation_date,
+        "modification_date": flag.modification_date,
+        "name": flag.name,
+        "status": flag.status,
+        "setter": flag.setter,
+        "requestee": flag.requestee,
+    });
+
+    merge_fields(&mut value, &flag.extra);
+    value
+}
+
+/// Convert a Bugzilla version, which might be a single string or a list, to JSON.
+fn version_to_json(version: &Version) -> Value {
+    match version {
+        Version::One(version) => json!(version),
+        Version::Many(versions) => json!(versions),
+    }
+}
+
+/// Copy all fields from the `extra` JSON object into the `value` JSON object,
+/// such as the fields that `bugzilla_query` captures as `extra`.
+fn merge_fields(value: &mut Value, extra: &Value) {
+    if let (Some(object), Some(extra)) = (value.as_object_mut(), extra.as_object()) {
+        for (key, extra_value) in extra {
+            object.insert(key.clone(), extra_value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A user in the format of the Bugzilla REST API.
+    fn user(name: &str) -> Value {
+        json!({
+            "email": format!("{name}@example.com"),
+            "id": 7,
+            "name": format!("{name}@example.com"),
+            "real_name": name,
+            "active": true,
+        })
+    }
+
+    /// A bug in the format of the Bugzilla REST API, with a value in every field
+    /// that `bugzilla_query` knows, and with some extra fields.
+    fn bug() -> Value {
+        let mut value = json!({
+            "op_sys": "Linux",
+            "classification": "Red Hat",
+            "id": 1234,
+            "url": "https://example.com/upstream/1",
+            "creator": "reporter@example.com",
+            "creator_detail": user("reporter"),
+            "summary": "The service crashes",
+            "status": "CLOSED",
+            "estimated_time": 3,
+            "target_milestone": "rc",
+            "cc": ["watcher@example.com"],
+            "cc_detail": [user("watcher")],
+            "is_open": false,
+            "is_creator_accessible": true,
+            "docs_contact": "writer@example.com",
+            "docs_contact_detail": user("writer"),
+            "assigned_to": "developer@example.com",
+            "assigned_to_detail": user("developer"),
+        });
+        let rest = json!({
+            "resolution": "ERRATA",
+            "severity": "high",
+            "product": "Product",
+            "platform": "x86_64",
+            "last_change_time": "2023-02-03T04:05:06Z",
+            "remaining_time": 1,
+            "priority": "urgent",
+            "whiteboard": "triaged",
+            "creation_time": "2023-01-02T03:04:05Z",
+            "is_confirmed": true,
+            "qa_contact": "tester@example.com",
+            "qa_contact_detail": user("tester"),
+            "dupe_of": 1000,
+            "target_release": ["9.2.0"],
+            "actual_time": 2,
+            "component": ["kernel", "kernel-rt"],
+            "is_cc_accessible": true,
+            "version": "9.0",
+            "keywords": ["Triaged"],
+            "depends_on": [1200],
+            "blocks": [1300],
+            "see_also": ["https://example.com/see/also"],
+            "groups": ["private"],
+            "deadline": "2023-03-01",
+            "update_token": "token",
+            "work_time": 4,
+            "flags": [{
+                "id": 1,
+                "type_id": 2,
+                "creation_date": "2023-01-02T03:04:05Z",
+                "modification_date": "2023-01-03T03:04:05Z",
+                "name": "requires_doc_text",
+                "status": "+",
+                "setter": "writer@example.com",
+                "requestee": "developer@example.com",
+                "is_active": 1,
+            }],
+            "tags": ["release-note"],
+            "dependent_products": ["Other product"],
+            "cf_release_notes": "The service no longer crashes.",
+            "pool": {"name": "Kernel team"},
+            "comments": [{"count": 0, "text": "The description."}],
+        });
+
+        merge_fields(&mut value, &rest);
+        value
+    }
+
+    #[test]
+    fn bug_round_trip() -> color_eyre::Result<()> {
+        let original: Bug = serde_json::from_value(bug())?;
+
+        let serialized = bug_to_json(&original);
+        // The JSON is the same as from Bugzilla, so no field is lost or renamed.
+        assert_eq!(serialized, bug());
+
+        let parsed: Bug = serde_json::from_value(serialized)?;
+        assert_eq!(parsed, original);
+        Ok(())
+    }
+
+    #[test]
+    fn bug_keeps_only_the_description_comment() -> color_eyre::Result<()> {
+        let mut value = bug();
+        value["comments"] = json!([
+            {"count": 0, "text": "The description."},
+            {"count": 1, "text": "A later comment."},
+        ]);
+        let original: Bug = serde_json::from_value(value)?;
+
+        assert_eq!(bug_to_json(&original), bug());
+        Ok(())
+    }
+}
@@ -16,9 +16,10 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::convert::From;
 use std::default::Default;
+use std::fmt;
 use std::ops::Neg;
 
 use askama::Template;
@@ -92,6 +93,7 @@ impl From<&[Checks]> for OverallProgress {
 
 /// Calculate the percentage of a part in a total amount.
 /// Uses `usize` as input because it works with list lengths here.
+#[allow(clippy::cast_precision_loss)]
 fn percentage(part: usize, total: usize) -> f64 {
     (part as f64) / (total as f64) * 100.0
 }
@@ -107,7 +109,7 @@ struct WriterStats<'a> {
     incomplete: i32,
 }
 
-impl<'a> WriterStats<'a> {
+impl WriterStats<'_> {
     /// Update these writer statistics with data from a ticket and its release note.
     fn update(&mut self, checks: &Checks) {
         self.total += 1;
@@ -227,16 +229,31 @@ impl Checks {
 
 /// The status of a particular ticket property. It can be either okay,
 /// a non-serious warning with a message, or a serious error with a message.
-#[derive(Serialize)]
+#[derive(Default, Serialize)]
 enum Status {
+    #[default]
     Ok,
     Warning(String),
     Error(String),
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Self::Ok
+impl fmt::Display for Checks {
+    /// List the overall status and each particular check on a separate line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let items = [
+            ("Overall", &self.overall()),
+            ("Development", &self.development),
+            ("Doc type", &self.doc_type),
+            ("Doc text status", &self.doc_status),
+            ("Title and text", &self.title_and_text),
+            ("Target release", &self.target_release),
+        ];
+
+        for (name, status) in items {
+            writeln!(f, "* {name}: {}", status.message())?;
+        }
+
+        Ok(())
     }
 }
 
@@ -441,6 +458,15 @@ impl AbstractTicket {
         }
     }
 
+    /// Display the value of a custom field, or nothing if the ticket doesn't set it.
+    fn display_custom_field(&self, name: &str) -> String {
+        self.custom_fields
+            .get(name)
+            .and_then(Option::as_ref)
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+
     /// Display the status, and if closed, also the resolution.
     fn display_status(&self) -> String {
         // For closed tickets, attach the resolution to the status.
@@ -489,7 +515,7 @@ fn combined_releases(tickets: &[AbstractTicket]) -> Vec<&str> {
     // Releases are a list, and each ticket can have several of them.
     // Update the counter with the values in the lists, rather than
     // with the lists themselves as values.
-    for ticket in tickets.iter() {
+    for ticket in tickets {
         releases.update(ticket.target_releases.iter().map(String::as_str));
     }
 
@@ -500,6 +526,16 @@ fn combined_releases(tickets: &[AbstractTicket]) -> Vec<&str> {
         .collect()
 }
 
+/// List the names of the custom fields that any of the tickets has, in alphabetical order.
+fn custom_field_names(tickets: &[AbstractTicket]) -> Vec<&str> {
+    let names: BTreeSet<&str> = tickets
+        .iter()
+        .flat_map(|ticket| ticket.custom_fields.keys().map(String::as_str))
+        .collect();
+
+    names.into_iter().collect()
+}
+
 /// Display the list of releases or products as a string.
 /// If the list is empty, provide a placeholder instead.
 fn list_or_placeholder(list: &[&str], name: &str) -> String {
@@ -510,6 +546,14 @@ fn list_or_placeholder(list: &[&str], name: &str) -> String {
     }
 }
 
+/// Analyze a single ticket and report its checks as a plain-text list.
+///
+/// A single ticket doesn't provide enough context to guess the release
+/// of the whole project, so the target release check always passes here.
+pub fn ticket_checks(ticket: &AbstractTicket) -> String {
+    ticket.checks(&[]).to_string()
+}
+
 /// All the data that the status table needs to render.
 #[derive(Template, Serialize)] // this will generate the code...
 #[template(path = "status-table.html")] // using the template in this path, relative
@@ -520,6 +564,8 @@ struct StatusTableTemplate<'a> {
     overall_progress: OverallProgress,
     tickets_with_checks: &'a [(&'a AbstractTicket, &'a Checks)],
     per_writer_stats: &'a [WriterStats<'a>],
+    /// Each custom field has its own column in the table.
+    custom_columns: &'a [&'a str],
     generated_date: &'a str,
 }
 
@@ -552,12 +598,15 @@ pub fn analyze_status(tickets: &[AbstractTicket]) -> Result<(String, String)> {
 
     let writer_stats = calculate_writer_stats(&tickets_with_checks);
 
+    let custom_columns = custom_field_names(tickets);
+
     let status_table = StatusTableTemplate {
         products: &products_display,
         release: &releases_display,
         overall_progress,
         per_writer_stats: &writer_stats,
         tickets_with_checks: &tickets_with_checks,
+        custom_columns: &custom_columns,
         generated_date: &date_today,
     };
 
@@ -21,9 +21,11 @@ use std::fmt;
 
 use askama::Template;
 use color_eyre::{eyre::Context, Result};
+use serde_json::json;
 
-use crate::extra_fields::DocTextStatus;
-use crate::templating::DocumentVariant;
+use crate::config::OutputFormat;
+use crate::custom_templates::CustomTemplate;
+use crate::markdown;
 use crate::AbstractTicket;
 
 // TODO: We might want these to be configurable.
@@ -48,6 +50,13 @@ struct SummaryList<'a> {
     tickets_by_components: &'a [TicketsByComponent<'a>],
 }
 
+/// The appendix in the Markdown format.
+#[derive(Template)]
+#[template(path = "summary-list.md", escape = "none")]
+struct MarkdownSummaryList<'a> {
+    tickets_by_components: &'a [TicketsByComponent<'a>],
+}
+
 /// A wrapper around tickets components. It keeps all internal components separate
 /// in the `Internal` variant. External components are unchanged in the `External` variant.
 #[derive(Eq, Hash, PartialEq, PartialOrd, Ord)]
@@ -84,28 +93,29 @@ impl fmt::Display for PresentableComponent<'_> {
 }
 
 /// Group together all tickets by their component. Instead of full tickets, store just their signatures.
-fn groups<'a>(
-    tickets: &[&'a AbstractTicket],
-    variant: DocumentVariant,
-) -> Vec<TicketsByComponent<'a>> {
+///
+/// In AsciiDoc, the signatures refer to the release notes in the document. Markdown can't refer
+/// to a release note in another file, so in Markdown, the signatures link to the tickets.
+fn groups<'a>(tickets: &[&'a AbstractTicket], format: OutputFormat) -> Vec<TicketsByComponent<'a>> {
     // Use an intermediate `HashMap` for grouping.
     let mut components: HashMap<PresentableComponent, Vec<String>> = HashMap::new();
 
-    tickets
-        .iter()
-        // Only include tickets with an approved doc text.
-        // TODO: Include all tickets in the internal document variant.
-        .filter(|ticket| filter_doc_text(ticket, variant))
-        .for_each(|ticket| {
-            for component in &ticket.components {
-                let presentable = PresentableComponent::from(component);
-
-                components
-                    .entry(presentable)
-                    .and_modify(|c| c.push(ticket.xref()))
-                    .or_insert_with(|| vec![ticket.xref()]);
-            }
-        });
+    // The tickets are already limited to the ones that belong in the document variant.
+    for ticket in tickets {
+        let signature = match format {
+            OutputFormat::AsciiDoc => ticket.xref(),
+            OutputFormat::Markdown => markdown::from_asciidoc(&ticket.signature()),
+        };
+
+        for component in &ticket.components {
+            let presentable = PresentableComponent::from(component);
+
+            components
+                .entry(presentable)
+                .and_modify(|c| c.push(signature.clone()))
+                .or_insert_with(|| vec![signature.clone()]);
+        }
+    }
 
     // Convert the intermediate `HashMap` to the output `TicketsByComponent` format.
     components
@@ -117,34 +127,49 @@ fn groups<'a>(
         .collect()
 }
 
-/// A filter function that limits the tickets that are listed in the external document variant:
-///
-/// * In the external variant, only list tickets with an approved doc text.
-/// * In the internal variant, list all tickets.
-fn filter_doc_text(ticket: &AbstractTicket, variant: DocumentVariant) -> bool {
-    match variant {
-        DocumentVariant::Internal => true,
-        DocumentVariant::External => ticket.doc_text_status == DocTextStatus::Approved,
-    }
-}
-
-/// Produce an AsciiDoc appendix file that lists all tickets in the document
+/// Produce an appendix file that lists all tickets in the document
 /// by their component in a sorted table.
-pub fn appendix(tickets: &[&AbstractTicket], variant: DocumentVariant) -> Result<String> {
+///
+/// If the project provides a custom summary list template, render the appendix with it instead.
+pub fn appendix(
+    tickets: &[&AbstractTicket],
+    format: OutputFormat,
+    custom: Option<&CustomTemplate>,
+) -> Result<String> {
     // Prepare ticket signatures grouped by component.
-    let mut groups = groups(tickets, variant);
+    let mut groups = groups(tickets, format);
 
     // Sort the list by component name, alphabetically.
     // The 'other' group ends up at the very end, because it's a separate `enum` variant.
     groups.sort_unstable();
 
-    // Pass the component groups to the AsciiDoc template.
-    let template = SummaryList {
-        tickets_by_components: &groups,
-    };
+    if let Some(custom) = custom {
+        // The custom template receives the components already formatted for display.
+        let tickets_by_components: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                json!({
+                    "component": group.component.to_string(),
+                    "signatures": group.signatures,
+                })
+            })
+            .collect();
+
+        return custom
+            .render(&json!({ "tickets_by_components": tickets_by_components }))
+            .wrap_err("Failed to prepare the ticket appendix.");
+    }
 
-    // Render the template as a valid AsciiDoc string.
-    template
-        .render()
-        .wrap_err("Failed to prepare the ticket appendix.")
+    // Pass the component groups to the template and render it as a string.
+    match format {
+        OutputFormat::AsciiDoc => SummaryList {
+            tickets_by_components: &groups,
+        }
+        .render(),
+        OutputFormat::Markdown => MarkdownSummaryList {
+            tickets_by_components: &groups,
+        }
+        .render(),
+    }
+    .wrap_err("Failed to prepare the ticket appendix.")
 }
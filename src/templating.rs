@@ -16,18 +16,25 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
 
 use askama::Template;
-//use color_eyre::Result;
+use serde::Serialize;
 
-use crate::config;
+use crate::config::{self, OutputFormat, Variant};
+use crate::custom_templates::{CustomTemplate, ModuleTemplates};
+use crate::extra_fields::CustomValue;
+use crate::markdown;
+use crate::note::content_lines;
+use crate::single_file;
 use crate::ticket_abstraction::AbstractTicket;
 use crate::ticket_abstraction::TicketId;
 
 /// A leaf, reference module that contains release notes with no further nesting.
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "reference.adoc", escape = "none")]
 struct Leaf<'a> {
     id: &'a str,
@@ -36,8 +43,18 @@ struct Leaf<'a> {
     release_notes: &'a [String],
 }
 
-/// An assembly module that nests other assemblies or leaf reference modules.
+/// The leaf module in the Markdown format.
 #[derive(Template)]
+#[template(path = "reference.md", escape = "none")]
+struct MarkdownLeaf<'a> {
+    id: &'a str,
+    title: &'a str,
+    intro_abstract: &'a str,
+    release_notes: &'a [String],
+}
+
+/// An assembly module that nests other assemblies or leaf reference modules.
+#[derive(Template, Serialize)]
 #[template(path = "assembly.adoc", escape = "none")]
 struct Assembly<'a> {
     id: &'a str,
@@ -46,35 +63,134 @@ struct Assembly<'a> {
     includes: &'a [String],
 }
 
-/// The variant of the generated, output document:
-///
-/// * `External`: The external variant intended for publishing the release notes.
-/// * `Internal`: The debugging variant intended for preparing the release notes.
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub enum DocumentVariant {
-    External,
-    Internal,
+/// The assembly module in the Markdown format.
+#[derive(Template)]
+#[template(path = "assembly.md", escape = "none")]
+struct MarkdownAssembly<'a> {
+    id: &'a str,
+    title: &'a str,
+    intro_abstract: &'a str,
+    includes: &'a [String],
+}
+
+/// A module that has a built-in template in each output format.
+/// The module serializes to the variables of a custom template.
+trait ModuleTemplate: Serialize {
+    fn render_built_in(&self, format: OutputFormat) -> askama::Result<String>;
+}
+
+impl ModuleTemplate for Leaf<'_> {
+    fn render_built_in(&self, format: OutputFormat) -> askama::Result<String> {
+        match format {
+            OutputFormat::AsciiDoc => self.render(),
+            OutputFormat::Markdown => MarkdownLeaf {
+                id: self.id,
+                title: self.title,
+                intro_abstract: self.intro_abstract,
+                release_notes: self.release_notes,
+            }
+            .render(),
+        }
+    }
+}
+
+impl ModuleTemplate for Assembly<'_> {
+    fn render_built_in(&self, format: OutputFormat) -> askama::Result<String> {
+        match format {
+            OutputFormat::AsciiDoc => self.render(),
+            OutputFormat::Markdown => MarkdownAssembly {
+                id: self.id,
+                title: self.title,
+                intro_abstract: self.intro_abstract,
+                includes: self.includes,
+            }
+            .render(),
+        }
+    }
 }
 
 /// The representation of a module, before being finally rendered.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Module {
     pub file_name: String,
+    pub title: String,
     pub text: String,
+    /// The text of the module with its included modules in place of the include statements,
+    /// which the single-file document uses.
+    pub inlined_text: String,
     pub included_modules: Option<Vec<Self>>,
 }
 
 impl Module {
-    /// The AsciiDoc include statement to include this module elsewhere.
-    pub fn include_statement(&self) -> String {
-        format!("include::{}[leveloffset=+1]", &self.file_name)
+    /// The statement to include this module elsewhere.
+    ///
+    /// Markdown has no include statement, so the Markdown assembly links to the module instead.
+    pub fn include_statement(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::AsciiDoc => format!("include::{}[leveloffset=+1]", &self.file_name),
+            OutputFormat::Markdown => format!("* [{}]({})", &self.title, &self.file_name),
+        }
     }
 }
 
+/// A failure to form the document from the templates.
+#[derive(Debug)]
+pub enum TemplateError {
+    /// A filter needs the subsystems of a ticket, but the subsystems field of the ticket is invalid.
+    InvalidSubsystems {
+        ticket: String,
+        /// Describes what the filter belongs to, such as "the `Bug fixes` section".
+        owner: String,
+        error: String,
+    },
+    /// The module template failed to render.
+    Render { module: String, error: String },
+    /// The section combines options that don't work together.
+    InvalidSection { section: String, reason: String },
+    /// In the strict mode, some tickets are unused, or used more than once.
+    Usage {
+        unused: Vec<String>,
+        overused: Vec<String>,
+    },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSubsystems {
+                ticket,
+                owner,
+                error,
+            } => write!(
+                f,
+                "Invalid subsystems field in ticket {ticket}, which {owner} needs: {error}"
+            ),
+            Self::Render { module, error } => {
+                write!(f, "Failed to render the {module} module: {error}")
+            }
+            Self::InvalidSection { section, reason } => {
+                write!(f, "Invalid `{section}` section: {reason}")
+            }
+            Self::Usage { unused, overused } => {
+                write!(f, "The templates must use each ticket exactly once.")?;
+                if !unused.is_empty() {
+                    write!(f, "\nUnused tickets: {}", unused.join(", "))?;
+                }
+                if !overused.is_empty() {
+                    write!(f, "\nTickets used more than once: {}", overused.join(", "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
 /// Convert a section title to an ID that's sanitized for AsciiDoc and HTML.
 ///
 /// This function is taken from `newdoc` (<https://github.com/redhat-documentation/newdoc>).
-fn id_fragment(title: &str) -> String {
+pub fn id_fragment(title: &str) -> String {
     // The ID is all lower-case
     let mut title_with_replacements: String = title.to_lowercase();
 
@@ -153,47 +269,217 @@ fn id_fragment(title: &str) -> String {
     title_with_replacements
 }
 
+/// Render the module with the custom template from the project if it exists,
+/// or with the built-in template otherwise.
+fn render_module(
+    template: &impl ModuleTemplate,
+    format: OutputFormat,
+    custom: Option<&CustomTemplate>,
+    module: &str,
+) -> Result<String, TemplateError> {
+    let rendered = if let Some(custom) = custom {
+        serde_json::to_value(template)
+            .map_err(|error| error.to_string())
+            .and_then(|variables| {
+                custom
+                    .render(&variables)
+                    .map_err(|error| format!("{error:#}"))
+            })
+    } else {
+        template
+            .render_built_in(format)
+            .map_err(|error| error.to_string())
+    };
+
+    rendered.map_err(|error| TemplateError::Render {
+        module: module.to_string(),
+        error,
+    })
+}
+
+/// The number of times that the document uses each ticket.
+type TicketStats = HashMap<Rc<TicketId>, u32>;
+
+/// The state of the document that the sections share while they render, in the document order.
+struct RenderContext<'a> {
+    variant: &'a Variant,
+    format: OutputFormat,
+    /// The templates from the project that override the built-in module templates.
+    templates: &'a ModuleTemplates,
+    /// The number of times that each ticket appears in the sections rendered so far.
+    ticket_stats: TicketStats,
+    /// The tickets that no regular section includes, which the catch-all sections collect.
+    /// This is `None` while the regular sections are still being resolved.
+    uncaught: Option<Vec<Rc<TicketId>>>,
+}
+
+impl<'a> RenderContext<'a> {
+    fn new(
+        tickets: &[&AbstractTicket],
+        variant: &'a Variant,
+        format: OutputFormat,
+        templates: &'a ModuleTemplates,
+    ) -> Self {
+        // Initialize every ticket in the statistics with 0 usage.
+        // Later, the number increases each time that the ticket is used.
+        // Initializing with 0 rather than relying on each ticket's `entry` call
+        // is necessary for tickets that end up unused, because they wouldn't
+        // call `entry` at all, and would report nothing.
+        let ticket_stats = tickets
+            .iter()
+            .map(|ticket| (Rc::clone(&ticket.id), 0))
+            .collect();
+
+        Self {
+            variant,
+            format,
+            templates,
+            ticket_stats,
+            uncaught: None,
+        }
+    }
+
+    /// Check if an earlier section already includes the ticket.
+    fn is_placed(&self, ticket: &AbstractTicket) -> bool {
+        self.ticket_stats
+            .get(&ticket.id)
+            .is_some_and(|&count| count > 0)
+    }
+}
+
+/// The order of the release notes in a section.
+#[derive(Clone, Copy, Default)]
+struct Sorting {
+    by: config::SortBy,
+    order: config::SortOrder,
+}
+
+impl Sorting {
+    /// Sort the tickets in place. The sort is stable, so tickets that compare equal
+    /// keep the order from the tickets configuration.
+    fn sort(self, tickets: &mut [&AbstractTicket]) {
+        let compare: fn(&AbstractTicket, &AbstractTicket) -> Ordering = match self.by {
+            config::SortBy::None => return,
+            config::SortBy::Summary => {
+                |a, b| a.summary.to_lowercase().cmp(&b.summary.to_lowercase())
+            }
+            config::SortBy::Title => |a, b| sort_title(a).cmp(&sort_title(b)),
+            config::SortBy::Priority => {
+                |a, b| priority_rank(&a.priority).cmp(&priority_rank(&b.priority))
+            }
+            config::SortBy::Component => |a, b| {
+                let first_component = |ticket: &AbstractTicket| {
+                    ticket
+                        .components
+                        .first()
+                        .map(|component| component.to_lowercase())
+                };
+                first_component(a).cmp(&first_component(b))
+            },
+            config::SortBy::Id => |a, b| {
+                (a.id.tracker.to_string(), natural_key(&a.id.key))
+                    .cmp(&(b.id.tracker.to_string(), natural_key(&b.id.key)))
+            },
+        };
+
+        match self.order {
+            config::SortOrder::Ascending => tickets.sort_by(|a, b| compare(a, b)),
+            config::SortOrder::Descending => tickets.sort_by(|a, b| compare(b, a)),
+        }
+    }
+}
+
+/// The title of the release note, which is the first line of the doc text
+/// in the AsciiDoc block title format, such as `.Title`.
+/// Without a title, fall back on the summary of the ticket.
+fn sort_title(ticket: &AbstractTicket) -> String {
+    let title = content_lines(&ticket.doc_text)
+        .first()
+        .and_then(|line| line.trim_start().strip_prefix('.'))
+        .filter(|title| !title.starts_with(['.', ' ']))
+        .unwrap_or(&ticket.summary);
+
+    title.to_lowercase()
+}
+
+/// Rank the priority or severity of a ticket, with the most urgent first.
+/// The names differ between the trackers, so similar names share a rank.
+fn priority_rank(priority: &str) -> u8 {
+    match priority.to_lowercase().as_str() {
+        "blocker" | "urgent" => 0,
+        "critical" | "high" => 1,
+        "major" | "medium" => 2,
+        "normal" => 3,
+        "minor" | "low" => 4,
+        "trivial" => 5,
+        // Such as `unspecified` or `Undefined`.
+        _ => 6,
+    }
+}
+
+/// Split a ticket key into its text prefix and its trailing number,
+/// so that `PROJ-9` sorts before `PROJ-10`.
+fn natural_key(key: &str) -> (&str, u64) {
+    let prefix = key.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = key[prefix.len()..].parse().unwrap_or_default();
+
+    (prefix, number)
+}
+
 impl config::Section {
     /// Convert the body of the section into AsciiDoc text that will serve
     /// as the body of the resulting module.
     fn render(
         &self,
         id: &str,
-        tickets: &[&AbstractTicket],
-        variant: DocumentVariant,
-        ticket_stats: &mut HashMap<Rc<TicketId>, u32>,
-    ) -> Option<String> {
-        let matching_tickets: Vec<_> = tickets.iter().filter(|t| self.matches_ticket(t)).collect();
-
+        matching_tickets: &[&AbstractTicket],
+        context: &mut RenderContext<'_>,
+    ) -> Result<Option<String>, TemplateError> {
         // Record usage statistics for this leaf module
-        for ticket in &matching_tickets {
-            ticket_stats
+        for ticket in matching_tickets {
+            context
+                .ticket_stats
                 .entry(Rc::clone(&ticket.id))
                 .and_modify(|counter| *counter += 1)
                 .or_insert(1);
         }
 
         if matching_tickets.is_empty() {
-            None
+            Ok(None)
         } else {
-            let release_notes: Vec<_> = matching_tickets
+            let file_name = format!("ref_{id}.{}", context.format.extension());
+            let release_notes = matching_tickets
                 .iter()
-                .map(|t| t.release_note(variant))
-                .collect();
+                .map(|t| {
+                    t.release_note(
+                        context.variant,
+                        context.format,
+                        context.templates.release_note.as_ref(),
+                    )
+                })
+                .collect::<color_eyre::Result<Vec<_>>>()
+                .map_err(|error| TemplateError::Render {
+                    module: file_name.clone(),
+                    error: format!("{error:#}"),
+                })?;
 
+            let intro_abstract = self.intro_abstract(context.format);
             let template = Leaf {
                 id,
                 title: &self.title,
                 // If an introductory abstract is configured for this section, add it below the heading.
-                intro_abstract: self.intro_abstract.as_ref().map_or("", |s| s.as_str()),
+                intro_abstract: &intro_abstract,
                 release_notes: &release_notes,
             };
 
-            Some(
-                template
-                    .render()
-                    .expect("Failed to render a reference module template."),
-            )
+            let text = render_module(
+                &template,
+                context.format,
+                context.templates.reference.as_ref(),
+                &file_name,
+            )?;
+
+            Ok(Some(text))
         }
     }
 
@@ -201,18 +487,20 @@ impl config::Section {
     /// the modules that it includes, recursively.
     ///
     /// Returns `None` if the module or assembly captured no release notes at all.
+    ///
+    /// The section inherits the sorting of its parent section, unless it configures its own.
     fn modules(
         &self,
         tickets: &[&AbstractTicket],
         prefix: Option<&str>,
-        variant: DocumentVariant,
-        ticket_stats: &mut HashMap<Rc<TicketId>, u32>,
-    ) -> Option<Module> {
-        let matching_tickets: Vec<&AbstractTicket> = tickets
-            .iter()
-            .filter(|&&t| self.matches_ticket(t))
-            .copied()
-            .collect();
+        inherited_sorting: Sorting,
+        context: &mut RenderContext<'_>,
+    ) -> Result<Option<Module>, TemplateError> {
+        let mut matching_tickets = self.matching_tickets(tickets, context)?;
+        let sorting = Sorting {
+            by: self.sort_by.unwrap_or(inherited_sorting.by),
+            order: self.sort_order.unwrap_or(inherited_sorting.order),
+        };
 
         let module_id_fragment = id_fragment(&self.title);
         let module_id = if let Some(prefix) = prefix {
@@ -221,112 +509,382 @@ impl config::Section {
             module_id_fragment
         };
 
-        // If the section includes other sections, treat it as an assembly.
-        if let Some(sections) = &self.subsections {
-            let file_name = format!("assembly_{module_id}.adoc");
-            let included_modules: Vec<Module> = sections
-                .iter()
-                .filter_map(|s| {
-                    s.modules(&matching_tickets, Some(&module_id), variant, ticket_stats)
-                })
-                .collect();
-            // If the assembly receives no modules, because all its modules are empty, return None.
-            if included_modules.is_empty() {
-                None
-            } else {
-                let include_statements: Vec<String> = included_modules
-                    .iter()
-                    .map(Module::include_statement)
-                    .collect();
+        // If the section groups its tickets, treat it as an assembly of a generated
+        // leaf module for each group.
+        if let Some(group_by) = self.group_by {
+            if self.subsections.is_some() {
+                return Err(TemplateError::InvalidSection {
+                    section: self.title.clone(),
+                    reason: "A section can't configure both `group_by` and `subsections`."
+                        .to_string(),
+                });
+            }
 
-                let template = Assembly {
-                    id: &module_id,
-                    title: &self.title,
-                    // If an introductory abstract is configured for this section, add it below the heading.
-                    intro_abstract: self.intro_abstract.as_ref().map_or("", |s| s.as_str()),
-                    includes: &include_statements,
+            let mut included_modules: Vec<Module> = Vec::new();
+            for (title, group_tickets) in self.groups(group_by, &matching_tickets)? {
+                let section = config::Section {
+                    title,
+                    ..config::Section::default()
                 };
-
-                let text = template
-                    .render()
-                    .expect("Failed to render an assembly template.");
-
-                Some(Module {
-                    file_name,
-                    text,
-                    included_modules: Some(included_modules),
-                })
+                if let Some(module) =
+                    section.modules(&group_tickets, Some(&module_id), sorting, context)?
+                {
+                    included_modules.push(module);
+                }
+            }
+            self.assembly(&module_id, included_modules, context)
+        // If the section includes other sections, treat it as an assembly.
+        } else if let Some(sections) = &self.subsections {
+            let mut included_modules: Vec<Module> = Vec::new();
+            for section in sections {
+                if let Some(module) =
+                    section.modules(&matching_tickets, Some(&module_id), sorting, context)?
+                {
+                    included_modules.push(module);
+                }
             }
+            self.assembly(&module_id, included_modules, context)
         // If the section includes no sections, treat it as a leaf, reference module.
         } else {
+            sorting.sort(&mut matching_tickets);
+
             // If the module receives no release notes and its body is empty, return None.
             // Otherwise, return the module formatted with its release notes.
-            self.render(&module_id, tickets, variant, ticket_stats)
+            let module = self
+                .render(&module_id, &matching_tickets, context)?
                 .map(|text| Module {
-                    file_name: format!("ref_{module_id}.adoc"),
+                    file_name: format!("ref_{module_id}.{}", context.format.extension()),
+                    title: self.title.clone(),
+                    inlined_text: text.clone(),
                     text,
                     included_modules: None,
-                })
+                });
+            Ok(module)
         }
     }
 
-    /// Checks whether this section, with its filter configuration, can include a particular ticket.
-    fn matches_ticket(&self, ticket: &AbstractTicket) -> bool {
-        let matches_doc_type = match &self.filter.doc_type {
-            Some(doc_types) => doc_types
-                .iter()
-                // Compare both doc types in lower case
-                // TODO: Turn the `expect` into proper error handling. See also the other variables below.
-                .any(|dt| dt.to_lowercase() == ticket.doc_type.to_lowercase()),
-            // If the filter doesn't configure a doc type, match by default
-            None => true,
+    /// Form an assembly that includes these modules.
+    ///
+    /// Returns `None` if the assembly receives no modules, because all its modules are empty.
+    fn assembly(
+        &self,
+        module_id: &str,
+        included_modules: Vec<Module>,
+        context: &RenderContext<'_>,
+    ) -> Result<Option<Module>, TemplateError> {
+        if included_modules.is_empty() {
+            return Ok(None);
+        }
+
+        let file_name = format!("assembly_{module_id}.{}", context.format.extension());
+        let intro_abstract = self.intro_abstract(context.format);
+
+        // Render the assembly once with the include statements, and once with the included
+        // modules embedded, so that custom templates can place the includes in any form.
+        let render = |includes: &[String]| {
+            let template = Assembly {
+                id: module_id,
+                title: &self.title,
+                // If an introductory abstract is configured for this section, add it below the heading.
+                intro_abstract: &intro_abstract,
+                includes,
+            };
+            render_module(
+                &template,
+                context.format,
+                context.templates.assembly.as_ref(),
+                &file_name,
+            )
         };
-        let matches_subsystem = match &self.filter.subsystem {
-            Some(ssts) => {
-                // Try to unwrap the result of the subsystems field only when a configured filter
-                // actually needs the subsystems. That way, subsystems are strictly optional,
-                // and if a project doesn't configure them at all, the release notes build
-                // can still finish successfully.
-                //
-                // TODO: Consider using a proper `Result` chain here instead of simply panicking.
-                let unwrapped_ssts = match &ticket.subsystems {
-                    Ok(ssts) => ssts,
-                    // If subsystems resulted in an error, print out some debugging information
-                    // before quitting. The ticket ID is especially useful.
-                    Err(e) => {
-                        log::error!("Invalid subsystems field in ticket {}.", &ticket.id);
-                        panic!("{}", e);
-                    }
-                };
 
-                ssts.iter()
-                    // Compare both subsystems in lower case.
-                    // Match if any of the ticket SSTs matches any of the template SSTs.
-                    .any(|sst| {
-                        unwrapped_ssts
-                            .iter()
-                            .any(|ticket_sst| sst.to_lowercase() == ticket_sst.to_lowercase())
+        let include_statements: Vec<String> = included_modules
+            .iter()
+            .map(|module| module.include_statement(context.format))
+            .collect();
+        let embedded_modules: Vec<String> = included_modules
+            .iter()
+            .map(|module| single_file::embed(&module.inlined_text, context.format))
+            .collect();
+
+        let text = render(&include_statements)?;
+        let inlined_text = render(&embedded_modules)?;
+
+        Ok(Some(Module {
+            file_name,
+            title: self.title.clone(),
+            text,
+            inlined_text,
+            included_modules: Some(included_modules),
+        }))
+    }
+
+    /// The introductory abstract of the section in the output format, or an empty string.
+    fn intro_abstract(&self, format: OutputFormat) -> String {
+        match (&self.intro_abstract, format) {
+            (None, _) => String::new(),
+            (Some(intro_abstract), OutputFormat::AsciiDoc) => intro_abstract.clone(),
+            (Some(intro_abstract), OutputFormat::Markdown) => {
+                markdown::from_asciidoc(intro_abstract)
+            }
+        }
+    }
+
+    /// Divide the tickets into groups by their values of the property,
+    /// and title each group using the configured titles.
+    ///
+    /// Values that have the same title share a group. A ticket with several values
+    /// appears in several groups, and a ticket with no value appears in none.
+    /// The groups are sorted by their titles.
+    fn groups<'a>(
+        &self,
+        group_by: config::GroupBy,
+        tickets: &[&'a AbstractTicket],
+    ) -> Result<Vec<(String, Vec<&'a AbstractTicket>)>, TemplateError> {
+        let mut groups: Vec<(String, Vec<&AbstractTicket>)> = Vec::new();
+
+        for &ticket in tickets {
+            let values: Vec<&str> = match group_by {
+                config::GroupBy::Component => {
+                    ticket.components.iter().map(String::as_str).collect()
+                }
+                config::GroupBy::Subsystem => ticket
+                    .subsystems
+                    .as_ref()
+                    .map_err(|error| TemplateError::InvalidSubsystems {
+                        ticket: ticket.id.to_string(),
+                        owner: format!("the `group_by` option of the `{}` section", self.title),
+                        error: error.clone(),
+                    })?
+                    .iter()
+                    .map(String::as_str)
+                    .collect(),
+                config::GroupBy::Label => {
+                    ticket.labels.iter().flatten().map(String::as_str).collect()
+                }
+                config::GroupBy::DocType => vec![ticket.doc_type.as_str()],
+            };
+
+            // Several values of the ticket can share a title.
+            let mut titles: Vec<String> = Vec::new();
+            for value in values {
+                let title = self.group_title(value);
+                if !titles.contains(&title) {
+                    titles.push(title);
+                }
+            }
+
+            for title in titles {
+                match groups.iter_mut().find(|(group, _tickets)| group == &title) {
+                    Some((_title, group_tickets)) => group_tickets.push(ticket),
+                    None => groups.push((title, vec![ticket])),
+                }
+            }
+        }
+
+        groups.sort_by_key(|(title, _tickets)| title.to_lowercase());
+
+        Ok(groups)
+    }
+
+    /// The title of the group for this value: either the configured title,
+    /// or the fallback title, where `{value}` stands for the value.
+    fn group_title(&self, value: &str) -> String {
+        let configured = self
+            .group_titles
+            .iter()
+            .find(|(configured_value, _title)| configured_value.eq_ignore_ascii_case(value))
+            .map(|(_value, title)| title.clone());
+
+        configured.unwrap_or_else(|| {
+            self.group_fallback
+                .as_deref()
+                .unwrap_or("{value}")
+                .replace("{value}", value)
+        })
+    }
+
+    /// Select the tickets that this section, with its filter configuration, includes.
+    ///
+    /// An exclusive section leaves out the tickets that earlier sections already include.
+    /// A catch-all section only includes the tickets that no regular section includes.
+    fn matching_tickets<'a>(
+        &self,
+        tickets: &[&'a AbstractTicket],
+        context: &RenderContext<'_>,
+    ) -> Result<Vec<&'a AbstractTicket>, TemplateError> {
+        let mut matching = Vec::new();
+
+        for &ticket in tickets {
+            let available = if self.catch_all {
+                context
+                    .uncaught
+                    .as_ref()
+                    .is_some_and(|uncaught| uncaught.contains(&ticket.id))
+            } else {
+                true
+            };
+            let repeated = self.exclusive && context.is_placed(ticket);
+
+            if available && !repeated && self.matches_ticket(ticket)? {
+                matching.push(ticket);
+            }
+        }
+
+        Ok(matching)
+    }
+
+    /// Checks whether this section, with its filter configuration, can include a particular ticket.
+    fn matches_ticket(&self, ticket: &AbstractTicket) -> Result<bool, TemplateError> {
+        self.filter.matches(
+            ticket,
+            &format!("the filter of the `{}` section", self.title),
+        )
+    }
+}
+
+/// Check if any of the patterns configured in the filter matches any of the ticket values.
+fn any_matches<'a>(
+    patterns: &[config::Pattern],
+    ticket_values: impl IntoIterator<Item = &'a str>,
+) -> bool {
+    let ticket_values: Vec<&str> = ticket_values.into_iter().collect();
+
+    patterns
+        .iter()
+        .any(|pattern| ticket_values.iter().any(|value| pattern.matches(value)))
+}
+
+impl config::Filter {
+    /// Checks whether the ticket matches all the conditions of this filter.
+    /// If the filter doesn't configure a condition, the condition matches by default.
+    ///
+    /// The owner describes what the filter belongs to, and only serves to identify the filter in an error.
+    pub fn matches(&self, ticket: &AbstractTicket, owner: &str) -> Result<bool, TemplateError> {
+        // Each list of patterns matches if any of the patterns matches any of the ticket values.
+        let lists: [(&Option<Vec<config::Pattern>>, Vec<&str>); 9] = [
+            (&self.doc_type, vec![ticket.doc_type.as_str()]),
+            (
+                &self.component,
+                ticket.components.iter().map(String::as_str).collect(),
+            ),
+            (
+                &self.label,
+                ticket.labels.iter().flatten().map(String::as_str).collect(),
+            ),
+            (&self.priority, vec![ticket.priority.as_str()]),
+            (&self.product, vec![ticket.product.as_str()]),
+            (
+                &self.target_release,
+                ticket.target_releases.iter().map(String::as_str).collect(),
+            ),
+            (&self.status, vec![ticket.status.as_str()]),
+            (
+                &self.doc_text_status,
+                ticket.doc_text_status.names().to_vec(),
+            ),
+            // A flag matches either by its complete value, such as `requires_doc_text: +`,
+            // or by its name, such as `requires_doc_text`.
+            (
+                &self.flag,
+                ticket
+                    .flags
+                    .iter()
+                    .flatten()
+                    .flat_map(|flag| {
+                        let name = flag.split(':').next().unwrap_or_default().trim();
+                        [flag.as_str(), name]
                     })
+                    .collect(),
+            ),
+        ];
+
+        for (filter_values, ticket_values) in lists {
+            if let Some(filter_values) = filter_values {
+                if !any_matches(filter_values, ticket_values) {
+                    return Ok(false);
+                }
             }
-            // If the filter doesn't configure a subsystem, match by default
-            None => true,
-        };
-        let matches_component = match &self.filter.component {
-            Some(components) => components
-                .iter()
-                // Compare both components in lower case
-                // Match if any of the ticket SSTs matches any of the template SSTs.
-                .any(|cmp| {
-                    ticket
-                        .components
-                        .iter()
-                        .any(|ticket_cmp| cmp.to_lowercase() == ticket_cmp.to_lowercase())
-                }),
-            // If the filter doesn't configure a component, match by default
-            None => true,
-        };
+        }
+
+        if !self.matches_custom_fields(ticket) {
+            return Ok(false);
+        }
+
+        if let Some(public) = self.public {
+            if ticket.public != public {
+                return Ok(false);
+            }
+        }
+
+        if let Some(trackers) = &self.tracker {
+            if !trackers.contains(&ticket.id.tracker) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(ssts) = &self.subsystem {
+            // Try to unwrap the result of the subsystems field only when a configured filter
+            // actually needs the subsystems. That way, subsystems are strictly optional,
+            // and if a project doesn't configure them at all, the release notes build
+            // can still finish successfully.
+            let unwrapped_ssts =
+                ticket
+                    .subsystems
+                    .as_ref()
+                    .map_err(|error| TemplateError::InvalidSubsystems {
+                        ticket: ticket.id.to_string(),
+                        owner: owner.to_string(),
+                        error: error.clone(),
+                    })?;
+
+            // Match if any of the template SSTs matches any of the ticket SSTs.
+            if !any_matches(ssts, unwrapped_ssts.iter().map(String::as_str)) {
+                return Ok(false);
+            }
+        }
+
+        // The nested filters:
+        if let Some(excluded) = &self.not {
+            if excluded.matches(ticket, owner)? {
+                return Ok(false);
+            }
+        }
 
-        matches_doc_type && matches_subsystem && matches_component
+        if let Some(all) = &self.all {
+            for filter in all {
+                if !filter.matches(ticket, owner)? {
+                    return Ok(false);
+                }
+            }
+        }
+
+        if let Some(any) = &self.any {
+            let mut matches_any = false;
+            for filter in any {
+                if filter.matches(ticket, owner)? {
+                    matches_any = true;
+                    break;
+                }
+            }
+            if !matches_any {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Checks whether the custom fields of the ticket match the configured patterns, if any.
+    fn matches_custom_fields(&self, ticket: &AbstractTicket) -> bool {
+        self.custom.iter().flatten().all(|(name, patterns)| {
+            let values = ticket
+                .custom_fields
+                .get(name)
+                .and_then(Option::as_ref)
+                .map(CustomValue::values)
+                .unwrap_or_default();
+            any_matches(patterns, values.iter().map(String::as_str))
+        })
     }
 }
 
@@ -334,54 +892,99 @@ impl config::Section {
 pub fn format_document(
     tickets: &[&AbstractTicket],
     template: &config::Template,
-    variant: DocumentVariant,
-) -> Vec<Module> {
-    // Prepare a container for ticket usage statistics.
-    let mut ticket_stats = HashMap::new();
+    module_templates: &ModuleTemplates,
+    variant: &Variant,
+) -> Result<Vec<Module>, TemplateError> {
+    let (chapters, _ticket_stats) = resolve_document(tickets, template, module_templates, variant)?;
+    log::debug!("Chapters: {:#?}", chapters);
+
+    Ok(chapters)
+}
 
-    // Initialize every ticket in the statistics with 0 usage.
-    // Later, the number increases each time that the ticket is used.
-    // Initializing with 0 rather than relying on each ticket's `entry` call
-    // is necessary for tickets that end up unused, because they wouldn't
-    // call `entry` at all, and would report nothing.
-    for ticket in tickets.iter() {
-        ticket_stats.insert(Rc::clone(&ticket.id), 0);
+/// Report the tickets that the document doesn't use, or uses more than once.
+///
+/// The statistics cover all the tickets, regardless of the variants that select them.
+pub fn check_usage(
+    tickets: &[&AbstractTicket],
+    template: &config::Template,
+    module_templates: &ModuleTemplates,
+) -> Result<(), TemplateError> {
+    let all_tickets = Variant::internal();
+    let (_chapters, ticket_stats) =
+        resolve_document(tickets, template, module_templates, &all_tickets)?;
+
+    report_usage_statistics(&ticket_stats, template.strict)
+}
+
+/// Form all modules of the document, and count how many times the document uses each ticket.
+fn resolve_document(
+    tickets: &[&AbstractTicket],
+    template: &config::Template,
+    module_templates: &ModuleTemplates,
+    variant: &Variant,
+) -> Result<(Vec<Module>, TicketStats), TemplateError> {
+    // The catch-all sections depend on the tickets that all the other sections include,
+    // so first, resolve the document without them. Then, render the whole document again
+    // with the catch-all sections, in the configured order.
+    let mut context = RenderContext::new(tickets, variant, template.format, module_templates);
+    if template.has_catch_all() {
+        chapters(tickets, template, &mut context)?;
+
+        let uncaught = context
+            .ticket_stats
+            .iter()
+            .filter(|&(_id, &count)| count == 0)
+            .map(|(id, _count)| Rc::clone(id))
+            .collect();
+        context = RenderContext::new(tickets, variant, template.format, module_templates);
+        context.uncaught = Some(uncaught);
     }
 
+    let chapters = chapters(tickets, template, &mut context)?;
+
+    Ok((chapters, context.ticket_stats))
+}
+
+/// Form the modules of all chapters, skipping the empty chapters.
+fn chapters(
+    tickets: &[&AbstractTicket],
+    template: &config::Template,
+    context: &mut RenderContext<'_>,
+) -> Result<Vec<Module>, TemplateError> {
     // TODO: If no release notes trickle down into a chapter, the chapter is simply skipped.
     // However, includes from the manual RN content tend to target all chapters.
     // Figure out a solution. Perhaps an empty file to appease the include from outside?
-    let chapters: Vec<_> = template
-        .chapters
-        .iter()
-        .filter_map(|section| section.modules(tickets, None, variant, &mut ticket_stats))
-        .collect();
-    log::debug!("Chapters: {:#?}", chapters);
-
-    // A crude way to ensure that the statistics are only printed once, and not twice.
-    // TODO: Revisit, maybe return the value instead.
-    if variant == DocumentVariant::Internal {
-        report_usage_statistics(&ticket_stats);
+    let mut chapters = Vec::new();
+    for section in &template.chapters {
+        if let Some(module) = section.modules(tickets, None, Sorting::default(), context)? {
+            chapters.push(module);
+        }
     }
 
-    chapters
+    Ok(chapters)
 }
 
 /// Log statistics about tickets that haven't been used anywhere in the templates,
 /// or have been used more than once. Log both as warnings.
-fn report_usage_statistics(ticket_stats: &HashMap<Rc<TicketId>, u32>) {
-    let unused: Vec<String> = ticket_stats
+///
+/// In the strict mode, either case is an error.
+fn report_usage_statistics(ticket_stats: &TicketStats, strict: bool) -> Result<(), TemplateError> {
+    let mut unused: Vec<String> = ticket_stats
         .iter()
         .filter(|&(_k, &v)| v == 0)
         .map(|(k, _v)| Rc::clone(k).to_string())
         .collect();
 
-    let overused: Vec<String> = ticket_stats
+    let mut overused: Vec<String> = ticket_stats
         .iter()
         .filter(|&(_k, &v)| v > 1)
         .map(|(k, _v)| Rc::clone(k).to_string())
         .collect();
 
+    // The statistics have no order, so sort the tickets for a stable report.
+    unused.sort();
+    overused.sort();
+
     if !unused.is_empty() {
         log::warn!("Tickets unused in the templates:\n\t {}", unused.join(", "));
     }
@@ -392,4 +995,627 @@ fn report_usage_statistics(ticket_stats: &HashMap<Rc<TicketId>, u32>) {
             overused.join(", ")
         );
     }
+
+    if strict && !(unused.is_empty() && overused.is_empty()) {
+        Err(TemplateError::Usage { unused, overused })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::Result;
+
+    use super::*;
+    use crate::extra_fields::{CustomFields, DocTextStatus, DocsContact};
+    use crate::tracker_access::{Jira, Tracker};
+
+    /// A Jira ticket with this key and doc type, and with neutral values in the other fields.
+    fn ticket(key: &str, doc_type: &str) -> AbstractTicket {
+        AbstractTicket {
+            id: Rc::new(TicketId {
+                key: key.to_string(),
+                tracker: Jira::SERVICE,
+            }),
+            summary: format!("Summary of {key}"),
+            description: None,
+            doc_type: doc_type.to_string(),
+            doc_text: format!("Text of {key}."),
+            docs_contact: DocsContact(None),
+            status: "Closed".to_string(),
+            resolution: None,
+            is_open: false,
+            priority: "Normal".to_string(),
+            url: format!("https://issues.example.com/browse/{key}"),
+            assignee: None,
+            components: Vec::new(),
+            product: "Product".to_string(),
+            labels: None,
+            flags: None,
+            target_releases: Vec::new(),
+            subsystems: Ok(Vec::new()),
+            groups: None,
+            public: true,
+            doc_text_status: DocTextStatus::Approved,
+            references: None,
+            custom_fields: CustomFields::new(),
+        }
+    }
+
+    /// Resolve the document configured in the YAML text.
+    fn resolve(yaml: &str, tickets: &[AbstractTicket]) -> Result<(Vec<Module>, TicketStats)> {
+        let template: config::Template = serde_yaml::from_str(yaml)?;
+        let tickets: Vec<&AbstractTicket> = tickets.iter().collect();
+        let document = resolve_document(
+            &tickets,
+            &template,
+            &ModuleTemplates::default(),
+            &Variant::internal(),
+        )?;
+
+        Ok(document)
+    }
+
+    /// The keys of the tickets in each leaf module, by the module title, in the document order.
+    fn placements(modules: &[Module], tickets: &[AbstractTicket]) -> Vec<(String, Vec<String>)> {
+        let mut placements = Vec::new();
+
+        for module in modules {
+            if let Some(included) = &module.included_modules {
+                placements.append(&mut self::placements(included, tickets));
+            } else {
+                let keys = tickets
+                    .iter()
+                    .filter(|ticket| module.text.contains(&ticket.doc_text))
+                    .map(|ticket| ticket.id.key.clone())
+                    .collect();
+                placements.push((module.title.clone(), keys));
+            }
+        }
+
+        placements
+    }
+
+    /// The number of times that the document uses the ticket.
+    fn uses(stats: &TicketStats, key: &str) -> u32 {
+        stats
+            .iter()
+            .find(|(id, _count)| id.key == key)
+            .map_or(0, |(_id, count)| *count)
+    }
+
+    #[test]
+    fn exclusive_section_skips_placed_tickets() -> Result<()> {
+        let tickets = [ticket("RN-1", "Bug Fix"), ticket("RN-2", "Enhancement")];
+        let yaml = r#"
+chapters:
+  - title: Fixes
+    filter:
+      doc_type: ["Bug Fix"]
+  - title: Everything
+  - title: Remaining
+    exclusive: true
+"#;
+        let (modules, stats) = resolve(yaml, &tickets)?;
+
+        assert_eq!(
+            placements(&modules, &tickets),
+            [
+                ("Fixes".to_string(), vec!["RN-1".to_string()]),
+                (
+                    "Everything".to_string(),
+                    vec!["RN-1".to_string(), "RN-2".to_string()]
+                ),
+            ]
+        );
+        // The regular section repeats the ticket, and the exclusive section leaves it out.
+        assert_eq!(uses(&stats, "RN-1"), 2);
+        assert_eq!(uses(&stats, "RN-2"), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn exclusive_sections_share_a_ticket_that_matches_both() -> Result<()> {
+        let mut networking_fix = ticket("RN-1", "Bug Fix");
+        networking_fix.components = vec!["networking".to_string()];
+        let tickets = [networking_fix];
+        let yaml = r#"
+chapters:
+  - title: Fixes
+    exclusive: true
+    filter:
+      doc_type: ["Bug Fix"]
+  - title: Networking
+    exclusive: true
+    filter:
+      component: ["networking"]
+"#;
+        let (modules, stats) = resolve(yaml, &tickets)?;
+
+        assert_eq!(
+            placements(&modules, &tickets),
+            [("Fixes".to_string(), vec!["RN-1".to_string()])]
+        );
+        assert_eq!(uses(&stats, "RN-1"), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn catch_all_collects_the_leftovers() -> Result<()> {
+        let tickets = [
+            ticket("RN-1", "Bug Fix"),
+            ticket("RN-2", "Enhancement"),
+            ticket("RN-3", "Known Issue"),
+        ];
+        // The catch-all section comes first, so it only knows the leftovers
+        // after the first pass over the whole document.
+        let yaml = r#"
+chapters:
+  - title: Other changes
+    catch_all: true
+  - title: Features
+    subsections:
+      - title: Enhancements
+        filter:
+          doc_type: ["Enhancement"]
+  - title: Fixes
+    filter:
+      doc_type: ["Bug Fix"]
+"#;
+        let (modules, stats) = resolve(yaml, &tickets)?;
+
+        assert_eq!(
+            placements(&modules, &tickets),
+            [
+                ("Other changes".to_string(), vec!["RN-3".to_string()]),
+                ("Enhancements".to_string(), vec!["RN-2".to_string()]),
+                ("Fixes".to_string(), vec!["RN-1".to_string()]),
+            ]
+        );
+        assert!(stats.values().all(|&count| count == 1));
+        Ok(())
+    }
+
+    #[test]
+    fn catch_all_applies_its_filter() -> Result<()> {
+        let tickets = [
+            ticket("RN-1", "Bug Fix"),
+            ticket("RN-2", "Enhancement"),
+            ticket("RN-3", "Known Issue"),
+        ];
+        let yaml = r#"
+chapters:
+  - title: Fixes
+    filter:
+      doc_type: ["Bug Fix"]
+  - title: Other enhancements
+    catch_all: true
+    filter:
+      doc_type: ["Enhancement"]
+"#;
+        let (modules, stats) = resolve(yaml, &tickets)?;
+
+        assert_eq!(
+            placements(&modules, &tickets),
+            [
+                ("Fixes".to_string(), vec!["RN-1".to_string()]),
+                ("Other enhancements".to_string(), vec!["RN-2".to_string()]),
+            ]
+        );
+        assert_eq!(uses(&stats, "RN-3"), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn strict_mode_rejects_unused_and_repeated_tickets() -> Result<()> {
+        let tickets = [
+            ticket("RN-1", "Bug Fix"),
+            ticket("RN-2", "Enhancement"),
+            ticket("RN-3", "Known Issue"),
+        ];
+        let all_tickets: Vec<&AbstractTicket> = tickets.iter().collect();
+        let yaml = r#"
+strict: true
+chapters:
+  - title: Fixes
+    filter:
+      doc_type: ["Bug Fix"]
+  - title: Everything except known issues
+    filter:
+      not:
+        doc_type: ["Known Issue"]
+"#;
+        let template: config::Template = serde_yaml::from_str(yaml)?;
+
+        let error = check_usage(&all_tickets, &template, &ModuleTemplates::default()).err();
+        let Some(TemplateError::Usage { unused, overused }) = error else {
+            panic!("The strict mode accepted the document: {error:?}");
+        };
+        assert_eq!(unused, ["Jira:RN-3"]);
+        assert_eq!(overused, ["Jira:RN-1"]);
+
+        // Without the strict mode, the same document only logs warnings.
+        let lenient: config::Template = serde_yaml::from_str(&yaml.replace("strict: true", ""))?;
+        check_usage(&all_tickets, &lenient, &ModuleTemplates::default())?;
+
+        // A catch-all section places the unused ticket and satisfies the strict mode.
+        let yaml = r#"
+strict: true
+chapters:
+  - title: Fixes
+    filter:
+      doc_type: ["Bug Fix"]
+  - title: Other changes
+    catch_all: true
+"#;
+        let caught: config::Template = serde_yaml::from_str(yaml)?;
+        check_usage(&all_tickets, &caught, &ModuleTemplates::default())?;
+
+        Ok(())
+    }
+
+    /// The keys of the tickets in their current order.
+    fn keys(tickets: &[&AbstractTicket]) -> Vec<String> {
+        tickets.iter().map(|ticket| ticket.id.key.clone()).collect()
+    }
+
+    #[test]
+    fn natural_order_of_keys() {
+        assert!(natural_key("RHEL-9") < natural_key("RHEL-10"));
+        assert!(natural_key("RHEL-10") < natural_key("RHEL-100"));
+        assert!(natural_key("OTHER-200") < natural_key("RHEL-1"));
+        assert_eq!(natural_key("no-number"), ("no-number", 0));
+
+        let tickets = [
+            ticket("RHEL-10", "Bug Fix"),
+            ticket("RHEL-100", "Bug Fix"),
+            ticket("RHEL-9", "Bug Fix"),
+        ];
+        let mut sorted: Vec<&AbstractTicket> = tickets.iter().collect();
+
+        let ascending = Sorting {
+            by: config::SortBy::Id,
+            order: config::SortOrder::Ascending,
+        };
+        ascending.sort(&mut sorted);
+        assert_eq!(keys(&sorted), ["RHEL-9", "RHEL-10", "RHEL-100"]);
+
+        let descending = Sorting {
+            by: config::SortBy::Id,
+            order: config::SortOrder::Descending,
+        };
+        descending.sort(&mut sorted);
+        assert_eq!(keys(&sorted), ["RHEL-100", "RHEL-10", "RHEL-9"]);
+    }
+
+    #[test]
+    fn priorities_of_all_trackers() {
+        assert_eq!(priority_rank("Urgent"), priority_rank("blocker"));
+        assert_eq!(priority_rank("HIGH"), priority_rank("critical"));
+        assert!(priority_rank("Major") < priority_rank("Normal"));
+        assert!(priority_rank("Low") < priority_rank("Trivial"));
+        // Unknown priorities sort after all the known ones.
+        assert!(priority_rank("Trivial") < priority_rank("unspecified"));
+        assert_eq!(priority_rank("Undefined"), priority_rank("Something else"));
+
+        let mut tickets = [
+            ticket("RN-1", "Bug Fix"),
+            ticket("RN-2", "Bug Fix"),
+            ticket("RN-3", "Bug Fix"),
+            ticket("RN-4", "Bug Fix"),
+        ];
+        tickets[0].priority = "unspecified".to_string();
+        tickets[1].priority = "Low".to_string();
+        tickets[2].priority = "Urgent".to_string();
+        tickets[3].priority = "Mystery".to_string();
+        let mut sorted: Vec<&AbstractTicket> = tickets.iter().collect();
+
+        Sorting {
+            by: config::SortBy::Priority,
+            order: config::SortOrder::Ascending,
+        }
+        .sort(&mut sorted);
+        assert_eq!(keys(&sorted), ["RN-3", "RN-2", "RN-1", "RN-4"]);
+    }
+
+    #[test]
+    fn ties_keep_the_configured_order() {
+        let mut tickets = [
+            ticket("RN-3", "Bug Fix"),
+            ticket("RN-1", "Bug Fix"),
+            ticket("RN-2", "Bug Fix"),
+        ];
+        tickets[0].summary = "Same".to_string();
+        tickets[1].summary = "same".to_string();
+        tickets[2].summary = "Earlier".to_string();
+        let mut sorted: Vec<&AbstractTicket> = tickets.iter().collect();
+
+        Sorting {
+            by: config::SortBy::Summary,
+            order: config::SortOrder::Ascending,
+        }
+        .sort(&mut sorted);
+        assert_eq!(keys(&sorted), ["RN-2", "RN-3", "RN-1"]);
+
+        // The descending order reverses the comparison, not the configured order of the ties.
+        Sorting {
+            by: config::SortBy::Summary,
+            order: config::SortOrder::Descending,
+        }
+        .sort(&mut sorted);
+        assert_eq!(keys(&sorted), ["RN-3", "RN-1", "RN-2"]);
+
+        // Without a sort key, the tickets stay in the configured order.
+        Sorting::default().sort(&mut sorted);
+        assert_eq!(keys(&sorted), ["RN-3", "RN-1", "RN-2"]);
+    }
+
+    #[test]
+    fn sorting_by_title_falls_back_on_the_summary() {
+        let mut tickets = [ticket("RN-1", "Bug Fix"), ticket("RN-2", "Bug Fix")];
+        tickets[0].doc_text = ".Zebra crossing\nText.".to_string();
+        tickets[1].summary = "Aardvark".to_string();
+        tickets[1].doc_text = "Text without a title.".to_string();
+        let mut sorted: Vec<&AbstractTicket> = tickets.iter().collect();
+
+        Sorting {
+            by: config::SortBy::Title,
+            order: config::SortOrder::Ascending,
+        }
+        .sort(&mut sorted);
+        assert_eq!(keys(&sorted), ["RN-2", "RN-1"]);
+    }
+
+    /// The titles of the groups with the keys of their tickets.
+    fn group_keys(groups: &[(String, Vec<&AbstractTicket>)]) -> Vec<(String, Vec<String>)> {
+        groups
+            .iter()
+            .map(|(title, tickets)| (title.clone(), keys(tickets)))
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_a_list_valued_field() -> Result<()> {
+        let mut tickets = [
+            ticket("RN-1", "Bug Fix"),
+            ticket("RN-2", "Bug Fix"),
+            ticket("RN-3", "Bug Fix"),
+            ticket("RN-4", "Bug Fix"),
+        ];
+        tickets[0].components = vec!["nfs".to_string(), "networking".to_string()];
+        tickets[1].components = vec!["kernel".to_string()];
+        // A ticket with no value appears in no group.
+        tickets[2].components = Vec::new();
+        // Two values with the same title put the ticket in the group once.
+        tickets[3].components = vec!["nfs".to_string(), "xfs".to_string()];
+        let tickets: Vec<&AbstractTicket> = tickets.iter().collect();
+
+        let section: config::Section = serde_yaml::from_str(
+            r#"
+title: Fixes
+group_by: component
+group_titles:
+  NFS: Storage
+  xfs: Storage
+  networking: Networking
+group_fallback: "The {value} component"
+"#,
+        )?;
+        let groups = section.groups(config::GroupBy::Component, &tickets)?;
+
+        // The groups are sorted by their titles, regardless of the ticket order.
+        assert_eq!(
+            group_keys(&groups),
+            [
+                ("Networking".to_string(), vec!["RN-1".to_string()]),
+                (
+                    "Storage".to_string(),
+                    vec!["RN-1".to_string(), "RN-4".to_string()]
+                ),
+                ("The kernel component".to_string(), vec!["RN-2".to_string()]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groups_with_missing_fields() -> Result<()> {
+        let mut tickets = [ticket("RN-1", "Bug Fix"), ticket("RN-2", "Enhancement")];
+        tickets[0].labels = Some(vec!["Zeta".to_string(), "alpha".to_string()]);
+        // The tracker doesn't provide labels.
+        tickets[1].labels = None;
+        let tickets: Vec<&AbstractTicket> = tickets.iter().collect();
+
+        let section = config::Section::default();
+        let groups = section.groups(config::GroupBy::Label, &tickets)?;
+        assert_eq!(
+            group_keys(&groups),
+            [
+                ("alpha".to_string(), vec!["RN-1".to_string()]),
+                ("Zeta".to_string(), vec!["RN-1".to_string()]),
+            ]
+        );
+
+        // Every ticket has a doc type.
+        let groups = section.groups(config::GroupBy::DocType, &tickets)?;
+        assert_eq!(
+            group_keys(&groups),
+            [
+                ("Bug Fix".to_string(), vec!["RN-1".to_string()]),
+                ("Enhancement".to_string(), vec!["RN-2".to_string()]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groups_by_invalid_subsystems() {
+        let mut broken = ticket("RN-1", "Bug Fix");
+        broken.subsystems = Err("The subsystem field is missing.".to_string());
+        let tickets = [&broken];
+
+        let section = config::Section {
+            title: "Fixes".to_string(),
+            ..config::Section::default()
+        };
+        let error = section.groups(config::GroupBy::Subsystem, &tickets).err();
+
+        assert!(
+            matches!(
+                &error,
+                Some(TemplateError::InvalidSubsystems { ticket, owner, .. })
+                    if ticket == "Jira:RN-1" && owner.contains("`Fixes`")
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn group_titles() -> Result<()> {
+        let section: config::Section = serde_yaml::from_str(
+            "
+title: Fixes
+group_titles:
+  kernel: Kernel
+",
+        )?;
+
+        assert_eq!(section.group_title("kernel"), "Kernel");
+        assert_eq!(section.group_title("KERNEL"), "Kernel");
+        // Without a fallback, the title is the value.
+        assert_eq!(section.group_title("nfs"), "nfs");
+        Ok(())
+    }
+
+    /// Check if the filter configured in the YAML text matches the ticket.
+    fn filter_matches(yaml: &str, ticket: &AbstractTicket) -> Result<bool> {
+        let filter: config::Filter = serde_yaml::from_str(yaml)?;
+        Ok(filter.matches(ticket, "the test filter")?)
+    }
+
+    /// A bug fix in the networking component that has the `regression` label.
+    fn networking_fix() -> AbstractTicket {
+        let mut ticket = ticket("RN-1", "Bug Fix");
+        ticket.components = vec!["networking".to_string()];
+        ticket.labels = Some(vec!["regression".to_string()]);
+        ticket
+    }
+
+    #[test]
+    fn filter_lists_and_not() -> Result<()> {
+        let ticket = networking_fix();
+
+        assert!(filter_matches("{}", &ticket)?);
+        assert!(filter_matches("doc_type: [Enhancement, Bug Fix]", &ticket)?);
+        assert!(!filter_matches(
+            "{doc_type: [Bug Fix], component: [kernel]}",
+            &ticket
+        )?);
+        assert!(!filter_matches("not: {label: [regression]}", &ticket)?);
+        assert!(filter_matches("not: {label: [upstream-only]}", &ticket)?);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_any_and_all() -> Result<()> {
+        let ticket = networking_fix();
+
+        assert!(filter_matches(
+            "any: [{component: [kernel]}, {label: [regression]}]",
+            &ticket
+        )?);
+        assert!(!filter_matches(
+            "any: [{component: [kernel]}, {label: [upstream-only]}]",
+            &ticket
+        )?);
+        assert!(filter_matches(
+            "all: [{component: [networking]}, {label: [regression]}]",
+            &ticket
+        )?);
+        assert!(!filter_matches(
+            "all: [{component: [networking]}, {label: [upstream-only]}]",
+            &ticket
+        )?);
+
+        // An empty `any` matches no ticket, and an empty `all` matches every ticket.
+        assert!(!filter_matches("any: []", &ticket)?);
+        assert!(filter_matches("all: []", &ticket)?);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_filters() -> Result<()> {
+        let ticket = networking_fix();
+        let nested = r#"
+any:
+  - doc_type: ["Known Issue"]
+  - all:
+      - component: ["network*"]
+      - not:
+          any:
+            - label: ["upstream-only"]
+            - priority: ["Urgent"]
+"#;
+        assert!(filter_matches(nested, &ticket)?);
+
+        let mut urgent = networking_fix();
+        urgent.priority = "urgent".to_string();
+        assert!(!filter_matches(nested, &urgent)?);
+
+        // The conditions next to the combinators still apply.
+        assert!(!filter_matches(
+            "{doc_type: [Enhancement], all: [{component: [networking]}]}",
+            &ticket
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_custom_fields() -> Result<()> {
+        let mut ticket = networking_fix();
+        ticket.custom_fields.insert(
+            "teams".to_string(),
+            Some(CustomValue::List(vec![
+                "Red".to_string(),
+                "Blue".to_string(),
+            ])),
+        );
+        ticket.custom_fields.insert(
+            "story_points".to_string(),
+            Some(CustomValue::Text("5".to_string())),
+        );
+        // The trackers configuration declares the field, but the ticket doesn't set it.
+        ticket.custom_fields.insert("severity".to_string(), None);
+
+        assert!(filter_matches("custom: {teams: [blue]}", &ticket)?);
+        assert!(!filter_matches("custom: {teams: [green]}", &ticket)?);
+        assert!(filter_matches(
+            "custom: {teams: [red], story_points: [\"5\"]}",
+            &ticket
+        )?);
+        assert!(!filter_matches(
+            "custom: {teams: [red], story_points: [\"8\"]}",
+            &ticket
+        )?);
+        assert!(!filter_matches("custom: {severity: [\"*\"]}", &ticket)?);
+        assert!(!filter_matches("custom: {undeclared: [\"*\"]}", &ticket)?);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_public_and_tracker() -> Result<()> {
+        let mut ticket = networking_fix();
+
+        assert!(filter_matches("public: true", &ticket)?);
+        assert!(!filter_matches("public: false", &ticket)?);
+        ticket.public = false;
+        assert!(filter_matches("public: false", &ticket)?);
+
+        assert!(filter_matches("tracker: [jira]", &ticket)?);
+        assert!(filter_matches("tracker: [Bugzilla, Jira]", &ticket)?);
+        assert!(!filter_matches("tracker: [BZ]", &ticket)?);
+        assert!(filter_matches("not: {tracker: [BZ]}", &ticket)?);
+        Ok(())
+    }
 }
@@ -16,27 +16,31 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::rc::Rc;
+use std::str::FromStr;
 use std::string::ToString;
 use std::sync::Arc;
 
 use bugzilla_query::{Bug, Component};
-use color_eyre::eyre::{bail, Result};
+use color_eyre::eyre::{bail, eyre, Error, Result};
 use jira_query::Issue;
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 
 use crate::config::{tracker, TicketQuery};
-use crate::extra_fields::{DocTextStatus, DocsContact, ExtraFields};
-use crate::tracker_access::{self, AnnotatedTicket};
+use crate::extra_fields::{bug_description, CustomFields, DocTextStatus, DocsContact, ExtraFields};
+use crate::forges::ForgeIssue;
+use crate::local_tickets::LocalTicket;
+use crate::tracker_access::{self, AnnotatedTicket, RawTickets, Tracker};
 
 /// An abstract ticket representation that generalizes over Bugzilla, Jira, and any other issue trackers.
 #[derive(Clone, Debug)]
 pub struct AbstractTicket {
     pub id: Rc<TicketId>,
     pub summary: String,
-    // TODO: Find out how to get the bug description from comment#0 with Bugzilla
+    /// In Bugzilla, the description is the text of comment #0.
     pub description: Option<String>,
     pub doc_type: String,
     pub doc_text: String,
@@ -61,6 +65,8 @@ pub struct AbstractTicket {
     pub public: bool,
     pub doc_text_status: DocTextStatus,
     pub references: Option<Vec<String>>,
+    /// The fields that the trackers configuration declares by name, on top of the standard ones.
+    pub custom_fields: CustomFields,
 }
 
 // This is a manual implementation of serde serialization purely because we can't
@@ -93,6 +99,7 @@ impl Serialize for AbstractTicket {
         state.serialize_field("groups", &self.groups)?;
         state.serialize_field("public", &self.public)?;
         state.serialize_field("references", &self.references)?;
+        state.serialize_field("custom_fields", &self.custom_fields)?;
         state.end()
     }
 }
@@ -110,6 +117,22 @@ impl fmt::Display for TicketId {
     }
 }
 
+impl FromStr for TicketId {
+    type Err = Error;
+
+    /// Parse the ID from its display format, such as `Jira:PROJECT-123`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (tracker, key) = s
+            .split_once(':')
+            .ok_or_else(|| eyre!("Invalid ticket ID: {:?}", s))?;
+
+        Ok(Self {
+            key: key.to_string(),
+            tracker: tracker.parse()?,
+        })
+    }
+}
+
 pub trait IntoAbstract {
     /// Converts a Bugzilla bug or a Jira ticket to `AbstractTicket`.
     /// Consumes the original ticket.
@@ -129,16 +152,16 @@ impl IntoAbstract for Bug {
         let ticket = AbstractTicket {
             id: Rc::new(TicketId {
                 key: self.id.to_string(),
-                tracker: tracker::Service::Bugzilla,
+                tracker: tracker_access::Bugzilla::SERVICE,
             }),
-            // TODO: Find out how to get the bug description from comment#0 with Bugzilla
-            description: None,
+            description: bug_description(&self),
             doc_type: self.doc_type(config)?,
             doc_text: self.doc_text(config)?,
             target_releases: self.target_releases(config),
             subsystems: self.subsystems(config).map_err(|e| e.to_string()),
             doc_text_status: self.doc_text_status(config)?,
             docs_contact: self.docs_contact(config),
+            custom_fields: self.custom_fields(config),
             url: self.url(config),
             summary: self.summary,
             status: self.status,
@@ -181,13 +204,14 @@ impl IntoAbstract for Issue {
             target_releases: self.target_releases(config),
             doc_text_status: self.doc_text_status(config)?,
             docs_contact: self.docs_contact(config),
+            custom_fields: self.custom_fields(config),
             subsystems: self.subsystems(config).map_err(|e| e.to_string()),
             url: self.url(config),
             // The ID in particular is wrapped in Rc because it's involved in various filters
             // and comparisons where ownership is complicated.
             id: Rc::new(TicketId {
                 key: self.key,
-                tracker: tracker::Service::Jira,
+                tracker: tracker_access::Jira::SERVICE,
             }),
             summary: self.fields.summary,
             description: self.fields.description,
@@ -219,19 +243,134 @@ impl IntoAbstract for Issue {
     }
 }
 
-/// Process the configured ticket queries into abstract tickets,
+impl IntoAbstract for ForgeIssue {
+    fn into_abstract(
+        self,
+        references: Option<Vec<String>>,
+        config: &impl tracker::FieldsConfig,
+    ) -> Result<AbstractTicket> {
+        let ticket = AbstractTicket {
+            id: Rc::new(TicketId {
+                key: self.key(),
+                tracker: self.service,
+            }),
+            doc_type: self.doc_type(config)?,
+            doc_text: self.doc_text(config)?,
+            target_releases: self.target_releases(config),
+            doc_text_status: self.doc_text_status(config)?,
+            docs_contact: self.docs_contact(config),
+            custom_fields: self.custom_fields(config),
+            subsystems: self.subsystems(config).map_err(|e| e.to_string()),
+            url: self.url(config),
+            is_open: self.is_open(),
+            summary: self.title,
+            description: Some(self.body),
+            status: self.state,
+            // The forges only distinguish open and closed issues.
+            resolution: None,
+            // The forges have no priority field.
+            priority: "Missing".to_string(),
+            assignee: self.assignee,
+            // The forges have no components. The repository is the closest equivalent to a product.
+            components: Vec::new(),
+            product: self.project,
+            labels: Some(self.labels),
+            flags: None,
+            groups: None,
+            // Only GitLab can restrict access to a single issue.
+            public: !self.confidential,
+            references,
+        };
+
+        Ok(ticket)
+    }
+}
+
+impl IntoAbstract for LocalTicket {
+    /// Local tickets contain the final values of all fields, so they ignore the fields configuration,
+    /// except for the configured doc text status values.
+    fn into_abstract(
+        self,
+        references: Option<Vec<String>>,
+        config: &impl tracker::FieldsConfig,
+    ) -> Result<AbstractTicket> {
+        let fields = self.fields;
+        let doc_text_status = DocTextStatus::from_value(
+            &fields.doc_text_status,
+            config,
+            format!("local ticket {}", self.key),
+        )?;
+
+        let ticket = AbstractTicket {
+            doc_text_status,
+            public: fields.public.unwrap_or(fields.url.is_some()),
+            id: Rc::new(TicketId {
+                key: self.key,
+                tracker: tracker_access::LocalFiles::SERVICE,
+            }),
+            summary: fields.summary,
+            description: fields.description,
+            doc_type: fields.doc_type,
+            doc_text: fields.doc_text,
+            docs_contact: DocsContact(fields.docs_contact),
+            status: fields.status,
+            resolution: fields.resolution,
+            is_open: fields.is_open,
+            priority: fields.priority.unwrap_or_else(|| "Missing".to_string()),
+            url: fields.url.unwrap_or_default(),
+            assignee: fields.assignee,
+            components: fields.components,
+            product: fields.product,
+            labels: fields.labels,
+            flags: fields.flags,
+            target_releases: fields.target_releases,
+            subsystems: Ok(fields.subsystems),
+            groups: fields.groups,
+            references,
+            custom_fields: fields
+                .custom_fields
+                .into_iter()
+                .map(|(name, value)| (name, Some(value)))
+                .collect(),
+        };
+
+        Ok(ticket)
+    }
+}
+
+/// Process the raw tickets downloaded for the configured ticket queries into abstract tickets,
 /// sorted in the original order as found in the config file.
 pub fn from_queries(
     queries: &[Arc<TicketQuery>],
+    raw_tickets: RawTickets,
     trackers: &tracker::Config,
 ) -> Result<Vec<AbstractTicket>> {
-    let annotated_tickets = tracker_access::unsorted_tickets(queries, trackers)?;
+    let annotated_tickets = tracker_access::unsorted_tickets(raw_tickets, trackers)?;
 
     // Sort the tickets according to the order in the config file.
     let sorted_tickets = sort_tickets(queries, &annotated_tickets)?;
 
     // Strip the query from the ticket. The query has served its full purpose.
-    Ok(sorted_tickets.into_iter().map(|at| at.ticket).collect())
+    let mut tickets: Vec<AbstractTicket> = sorted_tickets.into_iter().map(|at| at.ticket).collect();
+
+    unify_custom_fields(&mut tickets);
+
+    Ok(tickets)
+}
+
+/// Each tracker configures its own custom fields. List all of them in every ticket,
+/// so that templates and the status table can access any custom field in any ticket.
+fn unify_custom_fields(tickets: &mut [AbstractTicket]) {
+    let names: BTreeSet<String> = tickets
+        .iter()
+        .flat_map(|ticket| ticket.custom_fields.keys().cloned())
+        .collect();
+
+    for ticket in tickets {
+        for name in &names {
+            ticket.custom_fields.entry(name.clone()).or_insert(None);
+        }
+    }
 }
 
 /// Sort tickets to the order specified in the tickets configuration file.
@@ -16,16 +16,24 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::Path;
 use std::string::ToString;
 use std::sync::Arc;
 
 use bugzilla_query::Bug;
 use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use futures::future::{try_join_all, FutureExt, LocalBoxFuture};
 use jira_query::Issue;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 
-// use crate::config::tracker::Service;
 use crate::config::{tracker, KeyOrSearch, TicketQuery};
+use crate::forges::{ForgeClient, ForgeIssue};
+use crate::local_tickets::{LocalDirectory, LocalTicket};
 use crate::references::{ReferenceQueries, ReferenceSignatures};
+use crate::snapshot;
 use crate::ticket_abstraction::{AbstractTicket, IntoAbstract};
 
 /// The number of items in a single Jira query.
@@ -43,6 +51,12 @@ const BZ_API_KEY_VAR: &str = "BZ_API_KEY";
 /// The environment variable that holds the API key to Jira.
 const JIRA_API_KEY_VAR: &str = "JIRA_API_KEY";
 
+/// The environment variable that holds the API key to GitHub.
+const GITHUB_API_KEY_VAR: &str = "GITHUB_API_KEY";
+
+/// The environment variable that holds the API key to GitLab.
+const GITLAB_API_KEY_VAR: &str = "GITLAB_API_KEY";
+
 #[derive(Clone)]
 pub struct AnnotatedTicket {
     pub ticket: AbstractTicket,
@@ -69,51 +83,517 @@ impl AnnotatedTicket {
     }
 }
 
-/// Prepare a client to access Bugzilla.
-fn bz_instance(trackers: &tracker::Config) -> Result<bugzilla_query::BzInstance> {
-    let api_key = if let Some(key) = &trackers.bugzilla.api_key {
-        key.clone()
+/// Pick the API key to a tracker, in the order of precedence:
+///
+/// 1. The key specified on the command line, if any.
+/// 2. The key configured in the trackers configuration file, if any.
+/// 3. The key stored in the environment variable.
+fn api_key(
+    cli_key: Option<&str>,
+    configured_key: Option<&String>,
+    env_var: &str,
+) -> Result<String> {
+    if let Some(key) = cli_key {
+        Ok(key.to_string())
+    } else if let Some(key) = configured_key {
+        Ok(key.clone())
     } else {
-        // TODO: Store the name of the variable in a constant, or make it configurable.
-        std::env::var(BZ_API_KEY_VAR)
-            .wrap_err_with(|| format!("Set the {BZ_API_KEY_VAR} environment variable."))?
+        std::env::var(env_var).wrap_err_with(|| format!("Set the {env_var} environment variable."))
+    }
+}
+
+/// A ticket tracking service that the release notes can take tickets from.
+///
+/// Adding support for a new service means implementing this trait and listing
+/// the implementation in `TRACKERS`.
+pub trait Tracker: 'static {
+    /// The service that identifies this tracker in the configuration files.
+    const SERVICE: tracker::Service;
+    /// True if the tickets come from a remote service. The ticket snapshot only stores
+    /// the tickets of remote trackers, and an offline build reads the others directly.
+    const REMOTE: bool = true;
+    /// The section that configures this tracker in the trackers configuration file.
+    type Config: tracker::FieldsConfig + DeserializeOwned + fmt::Debug + 'static;
+    /// A client that accesses the tracker.
+    type Instance;
+    /// A raw ticket, as the tracker provides it.
+    type Ticket: IntoAbstract;
+
+    /// Select the configuration of this tracker, if the project configures it.
+    fn config(trackers: &tracker::Config) -> Option<&Self::Config> {
+        trackers.section(Self::SERVICE)
+    }
+
+    /// Resolve the relative paths in the configuration of this tracker
+    /// against the directory that contains the trackers configuration file.
+    fn resolve_paths(_config: &mut Self::Config, _config_dir: &Path) {}
+
+    /// Prepare a client to access the tracker.
+    ///
+    /// The optional `api_key` takes precedence over the API key configured for the tracker.
+    fn instance(config: &Self::Config, api_key: Option<&str>) -> Result<Self::Instance>;
+
+    /// Download the tickets that have these keys.
+    async fn fetch_keys(instance: &Self::Instance, keys: &[&str]) -> Result<Vec<Self::Ticket>>;
+
+    /// Download all tickets that match this search.
+    async fn search(instance: &Self::Instance, search: &str) -> Result<Vec<Self::Ticket>>;
+
+    /// The key of the ticket, in the same format as in the tickets configuration file.
+    fn key(ticket: &Self::Ticket) -> String;
+
+    /// Serialize the ticket for the ticket snapshot.
+    fn to_snapshot(ticket: &Self::Ticket) -> Result<Value>;
+
+    /// Load the ticket back from the ticket snapshot.
+    fn from_snapshot(value: Value) -> Result<Self::Ticket>;
+}
+
+/// Select the configuration of this tracker, or report that the project doesn't configure it.
+fn tracker_config<T: Tracker>(trackers: &tracker::Config) -> Result<&T::Config> {
+    T::config(trackers).ok_or_else(|| {
+        eyre!(
+            "The tickets require the {} tracker, but the trackers configuration file doesn't configure it.",
+            T::SERVICE
+        )
+    })
+}
+
+/// The Bugzilla tracker.
+pub struct Bugzilla;
+
+impl Tracker for Bugzilla {
+    const SERVICE: tracker::Service = tracker::Service {
+        name: "Bugzilla",
+        short_name: "BZ",
+        config_section: "bugzilla",
+        aliases: &[],
     };
+    type Config = tracker::BugzillaInstance;
+    type Instance = bugzilla_query::BzInstance;
+    type Ticket = Bug;
+
+    fn instance(config: &Self::Config, api_key: Option<&str>) -> Result<Self::Instance> {
+        let api_key = self::api_key(api_key, config.api_key.as_ref(), BZ_API_KEY_VAR)?;
+
+        let mut fields: Vec<String> = BZ_INCLUDED_FIELDS.iter().map(ToString::to_string).collect();
+        // The comments provide the bug description, which is comment #0.
+        // Bugzilla can only send all the comments, so request them only if the project
+        // uses the description.
+        if config.fields.description || config.fields.description_fallback {
+            fields.push("comments".to_string());
+        }
 
-    Ok(
-        bugzilla_query::BzInstance::at(trackers.bugzilla.host.clone())?
+        Ok(bugzilla_query::BzInstance::at(config.host.clone())?
             .authenticate(bugzilla_query::Auth::ApiKey(api_key))
             .paginate(bugzilla_query::Pagination::Unlimited)
-            .include_fields(BZ_INCLUDED_FIELDS.iter().map(ToString::to_string).collect()),
-    )
+            .include_fields(fields))
+    }
+
+    async fn fetch_keys(instance: &Self::Instance, keys: &[&str]) -> Result<Vec<Bug>> {
+        Ok(instance.bugs(keys).await?)
+    }
+
+    async fn search(instance: &Self::Instance, search: &str) -> Result<Vec<Bug>> {
+        Ok(instance.search(search).await?)
+    }
+
+    fn key(ticket: &Bug) -> String {
+        ticket.id.to_string()
+    }
+
+    fn to_snapshot(ticket: &Bug) -> Result<Value> {
+        Ok(snapshot::bug_to_json(ticket))
+    }
+
+    fn from_snapshot(value: Value) -> Result<Bug> {
+        serde_json::from_value(value).wrap_err("Failed to load a Bugzilla bug from the snapshot.")
+    }
 }
-/// Prepare a client to access Jira.
-fn jira_instance(trackers: &tracker::Config) -> Result<jira_query::JiraInstance> {
-    let api_key = if let Some(key) = &trackers.jira.api_key {
-        key.clone()
-    } else {
-        // TODO: Store the name of the variable in a constant, or make it configurable.
-        std::env::var(JIRA_API_KEY_VAR)
-            .wrap_err_with(|| format!("Set the {JIRA_API_KEY_VAR} environment variable."))?
+
+/// The Jira tracker.
+pub struct Jira;
+
+impl Tracker for Jira {
+    const SERVICE: tracker::Service = tracker::Service {
+        name: "Jira",
+        short_name: "Jira",
+        config_section: "jira",
+        aliases: &[],
     };
+    type Config = tracker::JiraInstance;
+    type Instance = jira_query::JiraInstance;
+    type Ticket = Issue;
+
+    fn instance(config: &Self::Config, api_key: Option<&str>) -> Result<Self::Instance> {
+        let api_key = self::api_key(api_key, config.api_key.as_ref(), JIRA_API_KEY_VAR)?;
+
+        Ok(jira_query::JiraInstance::at(config.host.clone())?
+            .authenticate(jira_query::Auth::ApiKey(api_key))
+            .paginate(jira_query::Pagination::ChunkSize(JIRA_CHUNK_SIZE)))
+    }
+
+    /// Request the issues through the search API. The single-issue API of `jira_query`
+    /// builds a request URL that Jira rejects.
+    async fn fetch_keys(instance: &Self::Instance, keys: &[&str]) -> Result<Vec<Issue>> {
+        Ok(instance.issues(keys).await?)
+    }
+
+    async fn search(instance: &Self::Instance, search: &str) -> Result<Vec<Issue>> {
+        Ok(instance.search(search).await?)
+    }
+
+    fn key(ticket: &Issue) -> String {
+        ticket.key.clone()
+    }
 
-    Ok(jira_query::JiraInstance::at(trackers.jira.host.clone())?
-        .authenticate(jira_query::Auth::ApiKey(api_key))
-        .paginate(jira_query::Pagination::ChunkSize(JIRA_CHUNK_SIZE)))
+    fn to_snapshot(ticket: &Issue) -> Result<Value> {
+        serde_json::to_value(ticket).wrap_err("Failed to serialize a Jira ticket.")
+    }
+
+    fn from_snapshot(value: Value) -> Result<Issue> {
+        serde_json::from_value(value).wrap_err("Failed to load a Jira ticket from the snapshot.")
+    }
+}
+
+/// Prepare a client to access a Git forge.
+///
+/// The forges allow reading public repositories without authentication,
+/// so the API key is optional.
+fn forge_instance<T: Tracker>(
+    config: &tracker::ForgeInstance,
+    cli_key: Option<&str>,
+    env_var: &str,
+) -> Result<ForgeClient> {
+    let api_key = api_key(cli_key, config.api_key.as_ref(), env_var).ok();
+
+    if api_key.is_none() {
+        log::debug!("Accessing {} without an API key.", T::SERVICE);
+    }
+
+    ForgeClient::at(T::SERVICE, &config.host, api_key)
+}
+
+/// The GitHub issue tracker.
+pub struct GitHub;
+
+impl Tracker for GitHub {
+    const SERVICE: tracker::Service = tracker::Service {
+        name: "GitHub",
+        short_name: "GitHub",
+        config_section: "github",
+        aliases: &["GH"],
+    };
+    type Config = tracker::ForgeInstance;
+    type Instance = ForgeClient;
+    type Ticket = ForgeIssue;
+
+    fn instance(config: &Self::Config, api_key: Option<&str>) -> Result<Self::Instance> {
+        forge_instance::<Self>(config, api_key, GITHUB_API_KEY_VAR)
+    }
+
+    async fn fetch_keys(instance: &Self::Instance, keys: &[&str]) -> Result<Vec<ForgeIssue>> {
+        instance.issues(keys).await
+    }
+
+    async fn search(instance: &Self::Instance, search: &str) -> Result<Vec<ForgeIssue>> {
+        instance.search(search).await
+    }
+
+    fn key(ticket: &ForgeIssue) -> String {
+        ticket.key()
+    }
+
+    fn to_snapshot(ticket: &ForgeIssue) -> Result<Value> {
+        serde_json::to_value(ticket).wrap_err("Failed to serialize a GitHub issue.")
+    }
+
+    fn from_snapshot(value: Value) -> Result<ForgeIssue> {
+        serde_json::from_value(value).wrap_err("Failed to load a GitHub issue from the snapshot.")
+    }
+}
+
+/// The GitLab issue tracker.
+pub struct GitLab;
+
+impl Tracker for GitLab {
+    const SERVICE: tracker::Service = tracker::Service {
+        name: "GitLab",
+        short_name: "GitLab",
+        config_section: "gitlab",
+        aliases: &["GL"],
+    };
+    type Config = tracker::ForgeInstance;
+    type Instance = ForgeClient;
+    type Ticket = ForgeIssue;
+
+    fn instance(config: &Self::Config, api_key: Option<&str>) -> Result<Self::Instance> {
+        forge_instance::<Self>(config, api_key, GITLAB_API_KEY_VAR)
+    }
+
+    async fn fetch_keys(instance: &Self::Instance, keys: &[&str]) -> Result<Vec<ForgeIssue>> {
+        instance.issues(keys).await
+    }
+
+    async fn search(instance: &Self::Instance, search: &str) -> Result<Vec<ForgeIssue>> {
+        instance.search(search).await
+    }
+
+    fn key(ticket: &ForgeIssue) -> String {
+        ticket.key()
+    }
+
+    fn to_snapshot(ticket: &ForgeIssue) -> Result<Value> {
+        serde_json::to_value(ticket).wrap_err("Failed to serialize a GitLab issue.")
+    }
+
+    fn from_snapshot(value: Value) -> Result<ForgeIssue> {
+        serde_json::from_value(value).wrap_err("Failed to load a GitLab issue from the snapshot.")
+    }
+}
+
+/// The tickets defined in local files, inside the release notes project.
+pub struct LocalFiles;
+
+impl Tracker for LocalFiles {
+    const SERVICE: tracker::Service = tracker::Service {
+        name: "File",
+        short_name: "File",
+        config_section: "file",
+        aliases: &["Local"],
+    };
+    /// The files are always available, and they might have changed since the last online build.
+    const REMOTE: bool = false;
+    type Config = tracker::FileInstance;
+    type Instance = LocalDirectory;
+    type Ticket = LocalTicket;
+
+    fn resolve_paths(config: &mut Self::Config, config_dir: &Path) {
+        config.directory = config_dir.join(&config.directory);
+    }
+
+    /// Local files need no API key.
+    fn instance(config: &Self::Config, _api_key: Option<&str>) -> Result<Self::Instance> {
+        LocalDirectory::at(&config.directory)
+    }
+
+    async fn fetch_keys(instance: &Self::Instance, keys: &[&str]) -> Result<Vec<LocalTicket>> {
+        instance.tickets(keys)
+    }
+
+    async fn search(instance: &Self::Instance, search: &str) -> Result<Vec<LocalTicket>> {
+        instance.search(search)
+    }
+
+    fn key(ticket: &LocalTicket) -> String {
+        ticket.key.clone()
+    }
+
+    fn to_snapshot(ticket: &LocalTicket) -> Result<Value> {
+        serde_json::to_value(ticket).wrap_err("Failed to serialize a local ticket.")
+    }
+
+    fn from_snapshot(value: Value) -> Result<LocalTicket> {
+        serde_json::from_value(value).wrap_err("Failed to load a local ticket from the snapshot.")
+    }
+}
+
+/// A raw ticket from any tracker, with the operations that the build needs after the download.
+pub trait RawTicket {
+    /// Converts the ticket to `AbstractTicket` using the configuration of its tracker.
+    /// Consumes the original ticket.
+    fn into_abstract(
+        self: Box<Self>,
+        references: Option<Vec<String>>,
+        trackers: &tracker::Config,
+    ) -> Result<AbstractTicket>;
+
+    /// Serialize the ticket for the ticket snapshot.
+    fn to_snapshot(&self) -> Result<Value>;
+}
+
+/// A wrapper that erases the tracker type of a raw ticket,
+/// so that tickets from all trackers can share a single list.
+struct Raw<T: Tracker> {
+    ticket: T::Ticket,
+    tracker: PhantomData<T>,
+}
+
+impl<T: Tracker> Raw<T> {
+    fn boxed(ticket: T::Ticket) -> Box<dyn RawTicket> {
+        Box::new(Self {
+            ticket,
+            tracker: PhantomData,
+        })
+    }
+}
+
+impl<T: Tracker> RawTicket for Raw<T> {
+    fn into_abstract(
+        self: Box<Self>,
+        references: Option<Vec<String>>,
+        trackers: &tracker::Config,
+    ) -> Result<AbstractTicket> {
+        self.ticket
+            .into_abstract(references, tracker_config::<T>(trackers)?)
+    }
+
+    fn to_snapshot(&self) -> Result<Value> {
+        T::to_snapshot(&self.ticket)
+    }
+}
+
+/// A tracker with its type erased, so that the build can pick it at run time
+/// based on the service of a ticket or of a configuration section.
+pub trait AnyTracker: Sync {
+    /// The service that identifies this tracker in the configuration files.
+    fn service(&self) -> tracker::Service;
+
+    /// True if the tickets come from a remote service, and the ticket snapshot stores them.
+    fn is_remote(&self) -> bool;
+
+    /// Parse the section that configures this tracker in the trackers configuration file.
+    fn parse_config(
+        &self,
+        value: serde_yaml::Value,
+    ) -> Result<Box<dyn tracker::Section>, serde_yaml::Error>;
+
+    /// Resolve the relative paths in the configuration of this tracker.
+    fn resolve_paths(&self, config: &mut dyn tracker::Section, config_dir: &Path);
+
+    /// Download the raw tickets and references that come from this tracker.
+    fn download<'a>(
+        &self,
+        queries: &'a [Arc<TicketQuery>],
+        ref_queries: &'a ReferenceQueries,
+        trackers: &'a tracker::Config,
+    ) -> LocalBoxFuture<'a, Result<RawTickets>>;
+
+    /// Download a single ticket from this tracker and convert it to an abstract ticket.
+    fn ticket<'a>(
+        &self,
+        key: &'a str,
+        api_key: Option<&'a str>,
+        trackers: &'a tracker::Config,
+    ) -> LocalBoxFuture<'a, Result<AbstractTicket>>;
+
+    /// Load a raw ticket of this tracker from the ticket snapshot.
+    fn raw_from_snapshot(&self, value: Value) -> Result<Box<dyn RawTicket>>;
+}
+
+/// The registration of a tracker implementation in `TRACKERS`.
+struct Registered<T>(PhantomData<fn() -> T>);
+
+impl<T: Tracker> AnyTracker for Registered<T> {
+    fn service(&self) -> tracker::Service {
+        T::SERVICE
+    }
+
+    fn is_remote(&self) -> bool {
+        T::REMOTE
+    }
+
+    fn parse_config(
+        &self,
+        value: serde_yaml::Value,
+    ) -> Result<Box<dyn tracker::Section>, serde_yaml::Error> {
+        let config: T::Config = serde_yaml::from_value(value)?;
+        Ok(Box::new(config))
+    }
+
+    fn resolve_paths(&self, config: &mut dyn tracker::Section, config_dir: &Path) {
+        if let Some(config) = config.as_any_mut().downcast_mut() {
+            T::resolve_paths(config, config_dir);
+        }
+    }
+
+    fn download<'a>(
+        &self,
+        queries: &'a [Arc<TicketQuery>],
+        ref_queries: &'a ReferenceQueries,
+        trackers: &'a tracker::Config,
+    ) -> LocalBoxFuture<'a, Result<RawTickets>> {
+        tracker_tickets::<T>(queries, ref_queries, trackers).boxed_local()
+    }
+
+    fn ticket<'a>(
+        &self,
+        key: &'a str,
+        api_key: Option<&'a str>,
+        trackers: &'a tracker::Config,
+    ) -> LocalBoxFuture<'a, Result<AbstractTicket>> {
+        single_ticket::<T>(key, api_key, trackers).boxed_local()
+    }
+
+    fn raw_from_snapshot(&self, value: Value) -> Result<Box<dyn RawTicket>> {
+        T::from_snapshot(value).map(Raw::<T>::boxed)
+    }
+}
+
+/// All the trackers that the release notes can take tickets from.
+/// The build downloads from them in this order.
+pub static TRACKERS: &[&dyn AnyTracker] = &[
+    &Registered::<Bugzilla>(PhantomData),
+    &Registered::<Jira>(PhantomData),
+    &Registered::<GitHub>(PhantomData),
+    &Registered::<GitLab>(PhantomData),
+    &Registered::<LocalFiles>(PhantomData),
+];
+
+/// Find the registered tracker of this service.
+pub fn registered(service: tracker::Service) -> Option<&'static dyn AnyTracker> {
+    TRACKERS
+        .iter()
+        .find(|tracker| tracker.service() == service)
+        .copied()
+}
+
+/// True if the tickets of this service come from a remote tracker, and the ticket snapshot stores them.
+pub fn is_remote(service: tracker::Service) -> bool {
+    registered(service).map_or(true, AnyTracker::is_remote)
+}
+
+/// Find the registered tracker of this service, or report that it's missing.
+fn tracker_of(service: tracker::Service) -> Result<&'static dyn AnyTracker> {
+    registered(service).ok_or_else(|| eyre!("The {service} tracker isn't supported."))
+}
+
+/// Load a raw ticket from the ticket snapshot, using the tracker that the ticket came from.
+pub fn raw_from_snapshot(service: tracker::Service, value: Value) -> Result<Box<dyn RawTicket>> {
+    tracker_of(service)?.raw_from_snapshot(value)
+}
+
+/// A raw ticket annotated with the query that it came from.
+pub type AnnotatedRaw = (Arc<TicketQuery>, Box<dyn RawTicket>);
+
+/// All the raw tickets downloaded from the trackers, before any processing.
+/// Each ticket is annotated with the query that it came from.
+#[derive(Default)]
+pub struct RawTickets {
+    pub plain: Vec<AnnotatedRaw>,
+    pub refs: Vec<AnnotatedRaw>,
+}
+
+impl RawTickets {
+    /// Move all the tickets from `other` into these tickets.
+    pub fn append(&mut self, mut other: Self) {
+        self.plain.append(&mut other.plain);
+        self.refs.append(&mut other.refs);
+    }
 }
 
 // TODO: Consider adding progress bars here. Investigate these libraries:
 // * https://crates.io/crates/progressing
 // * https://crates.io/crates/linya
 // * https://crates.io/crates/indicatif
-/// Process the configured ticket queries into abstract tickets,
-/// sorted in no particular order, which depends on the response from the issue tracker.
+/// Download the raw tickets for all the configured ticket queries and their references.
 ///
-/// Downloads from Bugzilla and from Jira in parallel.
+/// Downloads from all trackers in parallel.
 #[tokio::main]
-pub async fn unsorted_tickets(
+pub async fn download(
     queries: &[Arc<TicketQuery>],
     trackers: &tracker::Config,
-) -> Result<Vec<AnnotatedTicket>> {
+) -> Result<RawTickets> {
     // If no queries were found in the project configuration, quit with an error.
     // Such a situation should never occur because our config parsing requires at least
     // some items in the tickets file, but better make sure.
@@ -121,60 +601,79 @@ pub async fn unsorted_tickets(
         bail!("No tickets are configured in this project.");
     }
 
-    let queries: Vec<Arc<TicketQuery>> = queries.iter().map(Arc::clone).collect();
-
-    let ref_queries = ReferenceQueries::from(queries.as_slice());
+    download_from(TRACKERS.iter(), queries, trackers).await
+}
 
-    // Download from Bugzilla and from Jira in parallel:
-    let plain_bugs = bugs(QueriesKind::Plain(&queries), trackers);
-    let plain_issues = issues(QueriesKind::Plain(&queries), trackers);
-    let ref_bugs = bugs(QueriesKind::Ref(&ref_queries), trackers);
-    let ref_issues = issues(QueriesKind::Ref(&ref_queries), trackers);
+/// Load the raw tickets for the configured queries of the trackers that aren't remote,
+/// such as local files. The ticket snapshot leaves these tickets out.
+#[tokio::main]
+pub async fn load_local(
+    queries: &[Arc<TicketQuery>],
+    trackers: &tracker::Config,
+) -> Result<RawTickets> {
+    let local = TRACKERS.iter().filter(|tracker| !tracker.is_remote());
+    download_from(local, queries, trackers).await
+}
 
-    // Wait until both downloads have finished:
-    let (plain_bugs, plain_issues, ref_bugs, ref_issues) =
-        tokio::try_join!(plain_bugs, plain_issues, ref_bugs, ref_issues)?;
+/// Download the raw tickets and references from the selected trackers.
+async fn download_from(
+    selected: impl Iterator<Item = &'static &'static dyn AnyTracker>,
+    queries: &[Arc<TicketQuery>],
+    trackers: &tracker::Config,
+) -> Result<RawTickets> {
+    let ref_queries = ReferenceQueries::from(queries);
 
-    let ref_signatures = ReferenceSignatures::new(ref_bugs, ref_issues, trackers)?;
+    // Download from all trackers in parallel:
+    let downloads = selected.map(|tracker| tracker.download(queries, &ref_queries, trackers));
 
-    // Combine bugs and issues as abstract annotated tickets
-    let mut annotated_tickets = Vec::new();
-    annotated_tickets.append(&mut into_annotated_tickets(
-        plain_bugs,
-        &trackers.bugzilla,
-        &ref_signatures,
-    )?);
-    annotated_tickets.append(&mut into_annotated_tickets(
-        plain_issues,
-        &trackers.jira,
-        &ref_signatures,
-    )?);
+    // Wait until all downloads have finished:
+    let downloaded = try_join_all(downloads).await?;
 
-    // Modify each ticket by applying the overrides configured for it.
-    for annotated_ticket in &mut annotated_tickets {
-        annotated_ticket.override_fields();
+    let mut raw_tickets = RawTickets::default();
+    for tracker_tickets in downloaded {
+        raw_tickets.append(tracker_tickets);
     }
 
-    Ok(annotated_tickets)
+    Ok(raw_tickets)
 }
 
-/// Convert bugs and issues into abstract tickets.
-fn into_annotated_tickets(
-    issues: Vec<(Arc<TicketQuery>, impl IntoAbstract)>,
-    config: &impl tracker::FieldsConfig,
-    ref_signatures: &ReferenceSignatures,
+/// Download the raw tickets and references that come from a single tracker.
+async fn tracker_tickets<T: Tracker>(
+    queries: &[Arc<TicketQuery>],
+    ref_queries: &ReferenceQueries,
+    trackers: &tracker::Config,
+) -> Result<RawTickets> {
+    let plain = tickets::<T>(QueriesKind::Plain(queries), trackers);
+    let refs = tickets::<T>(QueriesKind::Ref(ref_queries), trackers);
+
+    let (plain, refs) = tokio::try_join!(plain, refs)?;
+
+    Ok(RawTickets { plain, refs })
+}
+
+/// Process the raw tickets into abstract tickets,
+/// sorted in no particular order, which depends on the response from the issue tracker.
+pub fn unsorted_tickets(
+    raw: RawTickets,
+    trackers: &tracker::Config,
 ) -> Result<Vec<AnnotatedTicket>> {
+    let ref_signatures = ReferenceSignatures::new(raw.refs, trackers)?;
+
     // Using an imperative style so that each `into_abstract` call can return an error.
-    let mut results = Vec::new();
+    let mut annotated_tickets = Vec::new();
 
-    for (query, issue) in issues {
+    for (query, raw_ticket) in raw.plain {
         let attached_references = ref_signatures.reattach_to(&query);
-        let ticket = issue.into_abstract(Some(attached_references), config)?;
-        let annotated = AnnotatedTicket { ticket, query };
-        results.push(annotated);
+        let ticket = raw_ticket.into_abstract(Some(attached_references), trackers)?;
+        annotated_tickets.push(AnnotatedTicket { ticket, query });
+    }
+
+    // Modify each ticket by applying the overrides configured for it.
+    for annotated_ticket in &mut annotated_tickets {
+        annotated_ticket.override_fields();
     }
 
-    Ok(results)
+    Ok(annotated_tickets)
 }
 
 /// Extract queries of the `TicketQuery::Key` kind with their keys.
@@ -234,219 +733,138 @@ impl QueriesKind<'_> {
     }
 }
 
-/// Download all configured bugs from Bugzilla.
-/// Returns every bug in a tuple, annotated with the query that it came from.
-async fn bugs(
+/// Download all configured tickets from a tracker.
+/// Returns every ticket in a tuple, annotated with the query that it came from.
+async fn tickets<T: Tracker>(
     queriesk: QueriesKind<'_>,
     trackers: &tracker::Config,
-) -> Result<Vec<(Arc<TicketQuery>, Bug)>> {
+) -> Result<Vec<AnnotatedRaw>> {
     let queries = queriesk.list();
-    let bugzilla_queries: Vec<Arc<TicketQuery>> = queries
+    let tracker_queries: Vec<Arc<TicketQuery>> = queries
         .iter()
-        .filter(|tq| tq.tracker == tracker::Service::Bugzilla)
+        .filter(|tq| tq.tracker == T::SERVICE)
         .map(Arc::clone)
         .collect();
 
-    // If no tickets target Bugzilla, skip the download and return an empty vector.
-    if bugzilla_queries.is_empty() {
+    // If no tickets target this tracker, skip the download and return an empty vector.
+    if tracker_queries.is_empty() {
         return Ok(Vec::new());
     }
 
-    let queries_by_id = take_id_queries(&bugzilla_queries);
-    let queries_by_search = take_search_queries(&bugzilla_queries);
-
-    log::info!("Downloading {} from Bugzilla.", queriesk.label());
-    let bz_instance = bz_instance(trackers)?;
-
-    let mut all_bugs = Vec::new();
+    let queries_by_id = take_id_queries(&tracker_queries);
+    let queries_by_search = take_search_queries(&tracker_queries);
 
-    let bugs_from_ids = bugs_from_ids(&queries_by_id, &bz_instance);
-    let bugs_from_searches = bugs_from_searches(&queries_by_search, &bz_instance);
+    log::info!("Downloading {} from {}.", queriesk.label(), T::SERVICE);
+    let instance = T::instance(tracker_config::<T>(trackers)?, None)?;
 
-    let (mut bugs_from_ids, mut bugs_from_searches) =
-        tokio::try_join!(bugs_from_ids, bugs_from_searches)?;
+    let mut all_tickets = Vec::new();
 
-    all_bugs.append(&mut bugs_from_ids);
-    all_bugs.append(&mut bugs_from_searches);
+    let tickets_from_ids = tickets_from_ids::<T>(&queries_by_id, &instance);
+    let tickets_from_searches = tickets_from_searches::<T>(&queries_by_search, &instance);
 
-    log::info!("Finished downloading {} from Bugzilla.", queriesk.label());
+    let (mut tickets_from_ids, mut tickets_from_searches) =
+        tokio::try_join!(tickets_from_ids, tickets_from_searches)?;
 
-    Ok(all_bugs)
-}
+    all_tickets.append(&mut tickets_from_ids);
+    all_tickets.append(&mut tickets_from_searches);
 
-/// Download bugs that come from ID queries.
-async fn bugs_from_ids(
-    queries: &[(&str, Arc<TicketQuery>)],
-    bz_instance: &bugzilla_query::BzInstance,
-) -> Result<Vec<(Arc<TicketQuery>, Bug)>> {
-    let bugs = bz_instance
-        .bugs(
-            &queries
-                .iter()
-                .map(|(key, _query)| *key)
-                .collect::<Vec<&str>>(),
-        )
-        // This enables the download concurrency:
-        .await
-        .wrap_err("Failed to download tickets from Bugzilla.")?;
-
-    let mut annotated_bugs: Vec<(Arc<TicketQuery>, Bug)> = Vec::new();
-
-    for bug in bugs {
-        let matching_query = queries
-            .iter()
-            .find(|(key, _query)| key == &bug.id.to_string().as_str())
-            .map(|(_key, query)| Arc::clone(query))
-            .ok_or_else(|| eyre!("Bug {} doesn't match any configured query.", bug.id))?;
-        annotated_bugs.push((matching_query, bug));
-    }
+    log::info!(
+        "Finished downloading {} from {}.",
+        queriesk.label(),
+        T::SERVICE
+    );
 
-    Ok(annotated_bugs)
+    Ok(all_tickets)
 }
 
-/// Download bugs that come from search queries.
-async fn bugs_from_searches(
+/// Download tickets that come from ID queries.
+async fn tickets_from_ids<T: Tracker>(
     queries: &[(&str, Arc<TicketQuery>)],
-    bz_instance: &bugzilla_query::BzInstance,
-) -> Result<Vec<(Arc<TicketQuery>, Bug)>> {
-    let mut annotated_bugs: Vec<(Arc<TicketQuery>, Bug)> = Vec::new();
-
-    for (search, query) in queries.iter() {
-        let mut bugs = bz_instance
-            .search(search)
-            // This enables the download concurrency:
-            .await
-            .wrap_err("Failed to download tickets from Bugzilla.")?
-            .into_iter()
-            .map(|bug| (Arc::clone(query), bug))
-            .collect();
-
-        annotated_bugs.append(&mut bugs);
-    }
-
-    Ok(annotated_bugs)
-}
-
-/// Download all configured issues from Jira.
-/// Returns every issue in a tuple, annotated with the query that it came from.
-async fn issues(
-    queriesk: QueriesKind<'_>,
-    trackers: &tracker::Config,
-) -> Result<Vec<(Arc<TicketQuery>, Issue)>> {
-    let queries = queriesk.list();
-    let jira_queries: Vec<Arc<TicketQuery>> = queries
-        .iter()
-        .filter(|&t| t.tracker == tracker::Service::Jira)
-        .map(Arc::clone)
-        .collect();
-
-    // If no tickets target Jira, skip the download and return an empty vector.
-    if jira_queries.is_empty() {
+    instance: &T::Instance,
+) -> Result<Vec<AnnotatedRaw>> {
+    // If no queries request tickets by ID, don't send an empty request to the tracker.
+    if queries.is_empty() {
         return Ok(Vec::new());
     }
 
-    let queries_by_id = take_id_queries(&jira_queries);
-    let queries_by_search = take_search_queries(&jira_queries);
-
-    log::info!("Downloading {} from Jira.", queriesk.label());
-
-    let jira_instance = jira_instance(trackers)?;
-
-    let mut all_issues = Vec::new();
-
-    let issues_from_ids = issues_from_ids(&queries_by_id, &jira_instance);
-    let issues_from_searches = issues_from_searches(&queries_by_search, &jira_instance);
-
-    let (mut issues_from_ids, mut issues_from_searches) =
-        tokio::try_join!(issues_from_ids, issues_from_searches)?;
-
-    all_issues.append(&mut issues_from_ids);
-    all_issues.append(&mut issues_from_searches);
-
-    log::info!("Finished downloading {} from Jira.", queriesk.label());
-
-    Ok(all_issues)
-}
-
-/// Download issues that come from ID queries.
-async fn issues_from_ids(
-    queries: &[(&str, Arc<TicketQuery>)],
-    jira_instance: &jira_query::JiraInstance,
-) -> Result<Vec<(Arc<TicketQuery>, Issue)>> {
-    let issues = jira_instance
-        .issues(
-            &queries
-                .iter()
-                .map(|(key, _query)| *key)
-                .collect::<Vec<&str>>(),
-        )
+    let keys: Vec<&str> = queries.iter().map(|(key, _query)| *key).collect();
+    let tickets = T::fetch_keys(instance, &keys)
         // This enables the download concurrency:
         .await
-        .wrap_err("Failed to download tickets from Jira.")?;
+        .wrap_err_with(|| format!("Failed to download tickets from {}.", T::SERVICE))?;
 
-    let mut annotated_issues: Vec<(Arc<TicketQuery>, Issue)> = Vec::new();
+    let mut annotated_tickets: Vec<AnnotatedRaw> = Vec::new();
 
-    for issue in issues {
+    for ticket in tickets {
+        let key = T::key(&ticket);
         let matching_query = queries
             .iter()
-            .find(|(key, _query)| key == &issue.key.as_str())
+            .find(|(query_key, _query)| query_key == &key.as_str())
             .map(|(_key, query)| Arc::clone(query))
-            .ok_or_else(|| eyre!("Issue {} doesn't match any configured query.", issue.key))?;
-        annotated_issues.push((matching_query, issue));
+            .ok_or_else(|| {
+                eyre!(
+                    "Ticket {}:{} doesn't match any configured query.",
+                    T::SERVICE,
+                    key
+                )
+            })?;
+        annotated_tickets.push((matching_query, Raw::<T>::boxed(ticket)));
     }
 
-    Ok(annotated_issues)
+    Ok(annotated_tickets)
 }
 
-/// Download issues that come from search queries.
-async fn issues_from_searches(
+/// Download tickets that come from search queries.
+async fn tickets_from_searches<T: Tracker>(
     queries: &[(&str, Arc<TicketQuery>)],
-    jira_instance: &jira_query::JiraInstance,
-) -> Result<Vec<(Arc<TicketQuery>, Issue)>> {
-    let mut annotated_issues: Vec<(Arc<TicketQuery>, Issue)> = Vec::new();
+    instance: &T::Instance,
+) -> Result<Vec<AnnotatedRaw>> {
+    let mut annotated_tickets: Vec<AnnotatedRaw> = Vec::new();
 
-    for (search, query) in queries.iter() {
-        let mut issues = jira_instance
-            .search(search)
+    for (search, query) in queries {
+        let mut tickets = T::search(instance, search)
             // This enables the download concurrency:
             .await
-            .wrap_err("Failed to download tickets from Jira.")?
+            .wrap_err_with(|| format!("Failed to download tickets from {}.", T::SERVICE))?
             .into_iter()
-            .map(|issue| (Arc::clone(query), issue))
+            .map(|ticket| (Arc::clone(query), Raw::<T>::boxed(ticket)))
             .collect();
 
-        annotated_issues.append(&mut issues);
+        annotated_tickets.append(&mut tickets);
     }
 
-    Ok(annotated_issues)
+    Ok(annotated_tickets)
 }
 
-// Temporarily disable this function while converting to configurable fields.
-/*
-/// Process a single ticket specified using the `ticket` subcommand.
+/// Download a single ticket specified using the `ticket` subcommand
+/// and convert it to an abstract ticket.
+///
+/// The optional `api_key` takes precedence over the API key configured for the tracker.
 #[tokio::main]
-pub async fn ticket<'a>(
-    id: &str,
-    api_key: &str,
-    service: Service,
-    tracker: &'a tracker::Instance,
-) -> Result<AbstractTicket<'a>> {
-    match service {
-        tracker::Service::Jira => {
-            let jira_instance = jira_query::JiraInstance::at(host.to_string())?
-                .authenticate(jira_query::Auth::ApiKey(api_key.to_string()))?;
-
-            let issue = jira_instance.issue(id).await?;
-            Ok(issue.into_abstract())
-        }
-        tracker::Service::Bugzilla => {
-            let bz_instance = bugzilla_query::BzInstance::at(host.to_string())?
-                .authenticate(bugzilla_query::Auth::ApiKey(api_key.to_string()))?
-                .include_fields(BZ_INCLUDED_FIELDS.iter().map(ToString::to_string).collect());
+pub async fn ticket(
+    key: &str,
+    api_key: Option<&str>,
+    service: tracker::Service,
+    trackers: &tracker::Config,
+) -> Result<AbstractTicket> {
+    tracker_of(service)?.ticket(key, api_key, trackers).await
+}
 
-            let bug = bz_instance.bug(id).await?;
-            Ok(bug.into_abstract())
-        }
-    }
+/// Download a single ticket from a tracker and convert it to an abstract ticket.
+async fn single_ticket<T: Tracker>(
+    key: &str,
+    api_key: Option<&str>,
+    trackers: &tracker::Config,
+) -> Result<AbstractTicket> {
+    let config = tracker_config::<T>(trackers)?;
+    let instance = T::instance(config, api_key)?;
+
+    let ticket = T::fetch_keys(&instance, &[key])
+        .await
+        .wrap_err_with(|| format!("Failed to download ticket {key} from {}.", T::SERVICE))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("Ticket {key} doesn't exist in {}.", T::SERVICE))?;
+
+    ticket.into_abstract(None, config)
 }
-*/